@@ -0,0 +1,197 @@
+use num::{BigInt, BigRational, CheckedAdd, CheckedSub, One, Zero};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SequenceError {
+    Empty,
+    Overflow,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the sequence is empty"),
+            Self::Overflow => write!(f, "the sequence overflowed its number type"),
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+/// The edges of a sequence's difference table, down to the first constant row.
+/// Only the first and last value of each row are kept, as that's all that's
+/// needed to extrapolate the sequence in either direction. All arithmetic is
+/// checked, so a fixed width `N` reports an overflow rather than wrapping.
+///
+/// A row with a single value counts as constant, so every sequence fits some
+/// polynomial. When that's the only constant row, the fit is just an
+/// interpolation of the values, which `is_confirmed` reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DifferenceTable<N = i64> {
+    first_column: Vec<N>,
+    last_column: Vec<N>,
+    confirmed: bool,
+}
+
+impl<N> DifferenceTable<N>
+where
+    N: Clone + PartialEq + CheckedAdd + CheckedSub + Into<BigInt>,
+{
+    pub fn new(sequence: &[N]) -> Result<Self, SequenceError> {
+        if sequence.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+
+        // Calculate each row of differences in place, shrinking as we go
+        let mut row = sequence.to_vec();
+        let mut first_column = Vec::new();
+        let mut last_column = Vec::new();
+        loop {
+            first_column.push(row[0].clone());
+            last_column.push(row[row.len() - 1].clone());
+            if row.iter().all(|n| *n == row[0]) {
+                break;
+            }
+            for i in 0..(row.len() - 1) {
+                row[i] = row[i + 1]
+                    .checked_sub(&row[i])
+                    .ok_or(SequenceError::Overflow)?;
+            }
+            row.pop();
+        }
+
+        Ok(Self {
+            first_column,
+            last_column,
+            confirmed: row.len() > 1,
+        })
+    }
+
+    pub const fn degree(&self) -> usize {
+        self.first_column.len() - 1
+    }
+
+    /// Whether the constant row had more than one value in it, so the
+    /// sequence really does follow a polynomial of this degree.
+    pub const fn is_confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    /// The next `steps` values of the sequence, in order.
+    pub fn extrapolate_forwards(&self, steps: usize) -> Result<Vec<N>, SequenceError> {
+        let mut column = self.last_column.clone();
+        (0..steps)
+            .map(|_| {
+                for level in (0..self.degree()).rev() {
+                    column[level] = column[level]
+                        .checked_add(&column[level + 1])
+                        .ok_or(SequenceError::Overflow)?;
+                }
+                Ok(column[0].clone())
+            })
+            .collect()
+    }
+
+    /// The previous `steps` values of the sequence, nearest first.
+    pub fn extrapolate_backwards(&self, steps: usize) -> Result<Vec<N>, SequenceError> {
+        let mut column = self.first_column.clone();
+        (0..steps)
+            .map(|_| {
+                for level in (0..self.degree()).rev() {
+                    column[level] = column[level]
+                        .checked_sub(&column[level + 1])
+                        .ok_or(SequenceError::Overflow)?;
+                }
+                Ok(column[0].clone())
+            })
+            .collect()
+    }
+
+    /// The coefficients of the fitted polynomial, from the constant term upwards,
+    /// where `x = 0` is the first value of the sequence.
+    pub fn coefficients(&self) -> Vec<BigRational> {
+        // Sum the Newton forward differences multiplied by the binomial polynomials
+        // C(x, k) = x(x - 1)...(x - k + 1) / k!
+        let mut coefficients = vec![BigRational::zero(); self.degree() + 1];
+        let mut binomial = vec![BigRational::one()];
+        for (k, difference) in self.first_column.iter().enumerate() {
+            let difference = BigRational::from_integer(difference.clone().into());
+            for (coefficient, term) in coefficients.iter_mut().zip(&binomial) {
+                *coefficient += &difference * term;
+            }
+
+            // Multiply by (x - k) / (k + 1) to get the next binomial polynomial
+            let k = BigRational::from_integer(BigInt::from(k));
+            let divisor = &k + BigRational::one();
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+            for (power, term) in binomial.iter().enumerate() {
+                next[power + 1] += term / &divisor;
+                next[power] -= term * &k / &divisor;
+            }
+            binomial = next;
+        }
+        coefficients
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rationals(numbers: &[(i64, i64)]) -> Vec<BigRational> {
+        numbers
+            .iter()
+            .map(|&(numer, denom)| BigRational::new(numer.into(), denom.into()))
+            .collect()
+    }
+
+    #[test]
+    fn extrapolate_several_steps() {
+        let table = DifferenceTable::new(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(table.degree(), 2);
+        assert_eq!(table.extrapolate_forwards(3), Ok(vec![28, 36, 45]));
+        assert_eq!(table.extrapolate_backwards(3), Ok(vec![0, 0, 1]));
+    }
+
+    #[test]
+    fn fitted_coefficients() {
+        // the triangular numbers (x + 1)(x + 2) / 2
+        let table = DifferenceTable::new(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(table.coefficients(), rationals(&[(1, 1), (3, 2), (1, 2)]));
+        // 2x^3 - 5x + 7
+        let table = DifferenceTable::new(&[7, 4, 13, 46, 115, 232]).unwrap();
+        assert_eq!(
+            table.coefficients(),
+            rationals(&[(7, 1), (-5, 1), (0, 1), (2, 1)])
+        );
+    }
+
+    #[test]
+    fn single_value_rows() {
+        // the powers of two only become constant once a row has one value left
+        let table = DifferenceTable::new(&[1, 2, 4, 8, 16]).unwrap();
+        assert_eq!(table.degree(), 4);
+        assert!(!table.is_confirmed());
+        assert_eq!(table.extrapolate_forwards(1), Ok(vec![31]));
+        let table = DifferenceTable::new(&[1, 3]).unwrap();
+        assert!(!table.is_confirmed());
+        assert_eq!(table.extrapolate_forwards(1), Ok(vec![5]));
+        let table = DifferenceTable::new(&[1, 3, 5]).unwrap();
+        assert!(table.is_confirmed());
+        let table = DifferenceTable::new(&[5]).unwrap();
+        assert_eq!(table.degree(), 0);
+        assert_eq!(table.extrapolate_backwards(2), Ok(vec![5, 5]));
+        let actual = DifferenceTable::<i64>::new(&[]);
+        assert_eq!(actual, Err(SequenceError::Empty));
+    }
+
+    #[test]
+    fn overflow() {
+        let actual = DifferenceTable::new(&[i64::MIN, 0, i64::MAX]);
+        assert_eq!(actual, Err(SequenceError::Overflow));
+        let table = DifferenceTable::new(&[i64::MAX - 3, i64::MAX - 2, i64::MAX - 1]).unwrap();
+        assert_eq!(table.extrapolate_forwards(1), Ok(vec![i64::MAX]));
+        assert_eq!(table.extrapolate_forwards(2), Err(SequenceError::Overflow));
+    }
+}
@@ -8,4 +8,13 @@ pub enum Direction {
 
 impl Direction {
     pub const NWSE: [Self; 4] = [Self::North, Self::West, Self::South, Self::East];
+
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
 }
@@ -2,7 +2,7 @@ use super::direction::Direction;
 
 pub type Point2D = (usize, usize);
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Grid2D {
     pub data: Vec<u8>,
     pub width: usize,
@@ -23,6 +23,15 @@ impl std::ops::IndexMut<Point2D> for Grid2D {
 }
 
 impl Grid2D {
+    /// Reads a grid of bytes from text, with one row per line.
+    pub fn from_lines(input: &str) -> Self {
+        Self {
+            width: input.lines().next().map_or(0, str::len),
+            height: input.lines().count(),
+            data: input.lines().flat_map(str::bytes).collect(),
+        }
+    }
+
     pub const fn move_from_point(&self, point: Point2D, dir: Direction) -> Option<Point2D> {
         match dir {
             Direction::North if point.1 > 0 => Some((point.0, point.1 - 1)),
@@ -0,0 +1,146 @@
+/// The Holiday ASCII String Helper algorithm, mapping any string to 0..256.
+pub fn hashify(input: &str) -> usize {
+    input
+        .bytes()
+        .fold(0, |acc, byte| ((acc + byte as usize) * 17) % 256)
+}
+
+/// A map of 256 boxes, where each box keeps its entries in insertion order.
+/// Replacing a value keeps its slot, and removing one shifts the rest forwards.
+#[derive(Clone, Debug)]
+pub struct HolidayHashMap<V> {
+    boxes: Vec<Vec<(String, V)>>,
+    hash: fn(&str) -> usize,
+}
+
+impl<V> Default for HolidayHashMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> HolidayHashMap<V> {
+    pub fn new() -> Self {
+        Self::with_hasher(hashify)
+    }
+
+    /// A map using a custom hash function, which is reduced modulo 256.
+    pub fn with_hasher(hash: fn(&str) -> usize) -> Self {
+        Self {
+            boxes: std::iter::repeat_with(Vec::new).take(256).collect(),
+            hash,
+        }
+    }
+
+    pub fn box_index(&self, label: &str) -> usize {
+        (self.hash)(label) % self.boxes.len()
+    }
+
+    fn slot_index(&self, label: &str) -> Option<usize> {
+        let hash_box = &self.boxes[self.box_index(label)];
+        hash_box.iter().position(|(other, _)| other == label)
+    }
+
+    /// Adds a value to the back of its box, or replaces the value in its existing
+    /// slot, returning the old value.
+    pub fn insert(&mut self, label: &str, value: V) -> Option<V> {
+        let box_index = self.box_index(label);
+        if let Some(slot) = self.slot_index(label) {
+            return Some(std::mem::replace(&mut self.boxes[box_index][slot].1, value));
+        }
+        self.boxes[box_index].push((label.to_owned(), value));
+        None
+    }
+
+    /// Replaces the value in an existing slot, doing nothing if the label is absent.
+    pub fn replace(&mut self, label: &str, value: V) -> Option<V> {
+        let box_index = self.box_index(label);
+        let slot = self.slot_index(label)?;
+        Some(std::mem::replace(&mut self.boxes[box_index][slot].1, value))
+    }
+
+    pub fn remove(&mut self, label: &str) -> Option<V> {
+        let box_index = self.box_index(label);
+        let slot = self.slot_index(label)?;
+        Some(self.boxes[box_index].remove(slot).1)
+    }
+
+    pub fn get(&self, label: &str) -> Option<&V> {
+        let slot = self.slot_index(label)?;
+        Some(&self.boxes[self.box_index(label)][slot].1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.boxes.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.iter().all(Vec::is_empty)
+    }
+
+    /// Every entry as `(box index, label, value)`, in box then slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str, &V)> {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(box_index, hash_box)| {
+                hash_box
+                    .iter()
+                    .map(move |(label, value)| (box_index, label.as_str(), value))
+            })
+    }
+}
+
+impl<V: Copy + Into<usize>> HolidayHashMap<V> {
+    /// The sum of each value multiplied by its 1-based box and slot numbers.
+    pub fn focusing_power(&self) -> usize {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(box_index, hash_box)| {
+                hash_box
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, &(_, value))| (box_index + 1) * (slot + 1) * value.into())
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn insert_replace_remove() {
+        let mut map = HolidayHashMap::new();
+        assert_eq!(map.insert("rn", 1_usize), None);
+        assert_eq!(map.insert("qp", 3), None);
+        assert_eq!(map.insert("cm", 2), None);
+        assert_eq!(map.insert("rn", 4), Some(1));
+        assert_eq!(map.replace("ot", 9), None);
+        assert_eq!(map.remove("qp"), Some(3));
+        assert_eq!(map.remove("qp"), None);
+        assert_eq!(map.get("rn"), Some(&4));
+        assert_eq!(map.len(), 2);
+        let actual = map.iter().collect_vec();
+        assert_eq!(actual, vec![(0, "rn", &4), (0, "cm", &2)]);
+        assert_eq!(map.focusing_power(), 8);
+    }
+
+    #[test]
+    fn custom_hasher() {
+        let mut map = HolidayHashMap::with_hasher(str::len);
+        map.insert("ccc", 3_u8);
+        map.insert("a", 1);
+        map.insert("bb", 2);
+        let actual = map
+            .iter()
+            .map(|(box_index, label, _)| (box_index, label))
+            .collect_vec();
+        assert_eq!(actual, vec![(1, "a"), (2, "bb"), (3, "ccc")]);
+        assert_eq!(map.focusing_power(), 2 + 6 + 12);
+    }
+}
@@ -1,4 +1,4 @@
-use num::Num;
+use num::{integer::ExtendedGcd, Integer, Num};
 
 pub fn abs_diff<N: Num + PartialOrd>(a: N, b: N) -> N {
     if a > b {
@@ -7,3 +7,21 @@ pub fn abs_diff<N: Num + PartialOrd>(a: N, b: N) -> N {
         b - a
     }
 }
+
+/// Combines `x ≡ a1 (mod m1)` and `x ≡ a2 (mod m2)` into a single congruence
+/// modulo `lcm(m1, m2)`, or returns `None` if no `x` satisfies both.
+/// The moduli multiply together as congruences are combined, so use a big
+/// integer for `N` unless they're known to stay small.
+pub fn combine_congruences<N: Integer + Clone>(
+    (a1, m1): (N, N),
+    (a2, m2): (N, N),
+) -> Option<(N, N)> {
+    let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+    let difference = a2 - a1.clone();
+    if !difference.is_multiple_of(&gcd) {
+        return None;
+    }
+    let lcm = m1.clone() / gcd.clone() * m2.clone();
+    let k = (difference / gcd.clone() * x).mod_floor(&(m2 / gcd));
+    Some(((a1 + m1 * k).mod_floor(&lcm), lcm))
+}
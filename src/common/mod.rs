@@ -1,4 +1,7 @@
+pub mod difference_table;
 pub mod direction;
 pub mod grid2d;
+pub mod holiday_hashmap;
 pub mod maths;
 pub mod parsing;
+pub mod polygon;
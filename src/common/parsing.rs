@@ -1,6 +1,7 @@
-use std::collections::HashSet;
+use num::BigInt;
+use std::{fmt::Debug, str::FromStr};
 
-pub fn parse_u32_set(input: &str) -> HashSet<u32> {
+pub fn parse_u32_vec(input: &str) -> Vec<u32> {
     let split = input.split_ascii_whitespace();
     split.map(str::parse).map(Result::unwrap).collect()
 }
@@ -15,11 +16,19 @@ pub fn parse_i64_vec(input: &str) -> Vec<i64> {
     split.map(str::parse).map(Result::unwrap).collect()
 }
 
-pub fn parse_f64_iter(input: &str) -> impl Iterator<Item = f64> + '_ {
+pub fn parse_u128_vec(input: &str) -> Vec<u128> {
     let split = input.split_ascii_whitespace();
-    split.map(str::parse).map(Result::unwrap)
+    split.map(str::parse).map(Result::unwrap).collect()
+}
+
+pub fn parse_bigint_vec(input: &str) -> Vec<BigInt> {
+    let split = input.split_ascii_whitespace();
+    split.map(str::parse).map(Result::unwrap).collect()
 }
 
-pub fn parse_f64_with_spaces(input: &str) -> f64 {
+pub fn parse_with_spaces<T: FromStr>(input: &str) -> T
+where
+    T::Err: Debug,
+{
     input.replace(' ', "").parse().unwrap()
 }
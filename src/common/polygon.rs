@@ -0,0 +1,66 @@
+use num::integer::gcd;
+
+pub type Vertex = (i64, i64);
+
+/// A simple lattice polygon, with its vertices in order around the boundary.
+/// The last vertex connects back round to the first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Polygon {
+    pub vertices: Vec<Vertex>,
+}
+
+impl Polygon {
+    pub const fn new(vertices: Vec<Vertex>) -> Self {
+        Self { vertices }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vertex, Vertex)> + '_ {
+        let next_vertices = self.vertices.iter().cycle().skip(1);
+        self.vertices.iter().copied().zip(next_vertices.copied())
+    }
+
+    /// Twice the enclosed area, using the shoelace formula.
+    /// Doubling keeps the result an integer for any lattice polygon.
+    pub fn double_area(&self) -> i64 {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+            .sum::<i64>()
+            .abs()
+    }
+
+    /// The number of lattice points on the boundary, including the vertices.
+    pub fn boundary_points(&self) -> i64 {
+        self.edges()
+            .map(|((x1, y1), (x2, y2))| gcd(x2 - x1, y2 - y1))
+            .sum()
+    }
+
+    /// The number of lattice points strictly inside, using Pick's theorem:
+    /// A = I + B / 2 - 1
+    pub fn interior_points(&self) -> i64 {
+        (self.double_area() - self.boundary_points()) / 2 + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn rectangle() {
+        let polygon = Polygon::new(vec![(0, 0), (6, 0), (6, 5), (0, 5)]);
+        assert_eq!(polygon.double_area(), 60);
+        assert_eq!(polygon.boundary_points(), 22);
+        assert_eq!(polygon.interior_points(), 20);
+    }
+
+    #[test]
+    fn diagonal_edges() {
+        // a right triangle with a hypotenuse passing through (2, 1)
+        let polygon = Polygon::new(vec![(0, 0), (4, 0), (4, 2)]);
+        assert_eq!(polygon.double_area(), 8);
+        assert_eq!(polygon.boundary_points(), 8);
+        assert_eq!(polygon.interior_points(), 1);
+    }
+}
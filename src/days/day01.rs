@@ -1,74 +1,196 @@
-use aho_corasick::{AhoCorasick, Match};
+use aho_corasick::{AhoCorasick, BuildError};
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use rayon::prelude::*;
+use std::{fmt, fs, io, path::Path, str::FromStr};
 
-const WORD_AND_DIGIT_PATTERNS: &[&str] = &[
-    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "0", "1", "2",
-    "3", "4", "5", "6", "7", "8", "9",
-];
+#[derive(Debug)]
+pub enum VocabularyError {
+    InvalidLine(usize),
+    InvalidDigit { line: usize, digit: String },
+    Automaton(BuildError),
+}
+
+impl fmt::Display for VocabularyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLine(line) => write!(f, "line {line} isn't a word and a digit"),
+            Self::InvalidDigit { line, digit } => {
+                write!(
+                    f,
+                    "line {line} has {digit:?}, which isn't a digit from 0 to 9"
+                )
+            }
+            Self::Automaton(error) => write!(f, "couldn't build the automaton: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for VocabularyError {}
+
+/// The words that stand for each digit in a calibration document.
+/// The digits themselves are always recognised as well.
+#[derive(Clone, Debug)]
+pub struct CalibrationVocabulary {
+    digits: Vec<u32>,
+    automaton: AhoCorasick,
+}
+
+impl CalibrationVocabulary {
+    pub fn new<'a>(words: impl IntoIterator<Item = (&'a str, u32)>) -> Result<Self, BuildError> {
+        let digit_chars = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        let (patterns, digits): (Vec<&str>, Vec<u32>) = words
+            .into_iter()
+            .chain(digit_chars.into_iter().zip(0..))
+            .unzip();
+        let automaton = AhoCorasick::new(patterns)?;
+        Ok(Self { digits, automaton })
+    }
+
+    fn from_words(words: [&str; 10]) -> Self {
+        Self::new(words.into_iter().zip(0..)).unwrap()
+    }
+
+    pub fn english() -> Self {
+        Self::from_words([
+            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ])
+    }
+
+    pub fn french() -> Self {
+        Self::from_words([
+            "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+        ])
+    }
+
+    pub fn german() -> Self {
+        Self::from_words([
+            "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+        ])
+    }
+
+    pub fn spanish() -> Self {
+        Self::from_words([
+            "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+        ])
+    }
+
+    /// Reads a vocabulary file, with a word and its digit on each line.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let input = fs::read_to_string(path)?;
+        input
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Every digit in a line, in order, including words that overlap like `eightwo`.
+    pub fn digits<'a>(&'a self, line: &'a str) -> impl Iterator<Item = u32> + 'a {
+        self.automaton
+            .find_overlapping_iter(line)
+            .map(|the_match| self.digits[the_match.pattern()])
+    }
+}
+
+/// One `word digit` pair per line, skipping blank lines and `#` comments.
+impl FromStr for CalibrationVocabulary {
+    type Err = VocabularyError;
+
+    fn from_str(input: &str) -> Result<Self, VocabularyError> {
+        let mut words = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (word, digit_str) = line
+                .split_once(char::is_whitespace)
+                .ok_or(VocabularyError::InvalidLine(i + 1))?;
+            let Ok(digit @ 0..=9) = digit_str.trim().parse() else {
+                return Err(VocabularyError::InvalidDigit {
+                    line: i + 1,
+                    digit: digit_str.trim().to_owned(),
+                });
+            };
+            words.push((word, digit));
+        }
+        Self::new(words).map_err(VocabularyError::Automaton)
+    }
+}
 
 lazy_static! {
-    static ref AHO_CORASICK: AhoCorasick = AhoCorasick::new(WORD_AND_DIGIT_PATTERNS).unwrap();
+    static ref DIGITS_ONLY: CalibrationVocabulary = CalibrationVocabulary::new([]).unwrap();
+    static ref ENGLISH: CalibrationVocabulary = CalibrationVocabulary::english();
 }
 
-fn parse_first_digit<I: Iterator<Item = char>>(mut chars: I) -> u32 {
-    chars
-        .find(char::is_ascii_digit)
-        .and_then(|c| c.to_digit(10))
-        .unwrap()
+/// A line with no recognisable digit, numbered from 1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DigitlessLine(pub usize);
+
+impl fmt::Display for DigitlessLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} has no recognisable digit", self.0)
+    }
 }
 
-fn parse_calibration_line_digits_only(input: &str) -> u32 {
-    let first_digit = parse_first_digit(input.chars());
-    let last_digit = parse_first_digit(input.chars().rev());
-    first_digit * 10 + last_digit
+impl std::error::Error for DigitlessLine {}
+
+/// The sum of every line that had a calibration value, along with the lines that didn't.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CalibrationReport {
+    pub sum: u32,
+    pub digitless_lines: Vec<usize>,
 }
 
-fn match_to_digit(the_match: Match) -> Option<u32> {
-    let string = WORD_AND_DIGIT_PATTERNS[the_match.pattern()];
-    match string {
-        "zero" => Some(0),
-        "one" => Some(1),
-        "two" => Some(2),
-        "three" => Some(3),
-        "four" => Some(4),
-        "five" => Some(5),
-        "six" => Some(6),
-        "seven" => Some(7),
-        "eight" => Some(8),
-        "nine" => Some(9),
-        x if let c = x.chars().next().unwrap()
-            && c.is_ascii_digit() =>
-        {
-            c.to_digit(10)
+impl CalibrationReport {
+    /// The sum, but only if every line had a calibration value.
+    pub fn strict(&self) -> Result<u32, DigitlessLine> {
+        match self.digitless_lines.first() {
+            Some(&line) => Err(DigitlessLine(line)),
+            None => Ok(self.sum),
         }
-        _ => None,
     }
 }
 
-fn parse_calibration_line_words_and_digits(input: &str) -> u32 {
-    let ac = AHO_CORASICK.clone();
-    let mut matches = ac.find_overlapping_iter(input);
-    let first_digit = matches.next().and_then(match_to_digit).unwrap();
-    let last_digit = matches
-        .last()
-        .and_then(match_to_digit)
-        .unwrap_or(first_digit);
-    first_digit * 10 + last_digit
+fn parse_calibration_line(input: &str, vocabulary: &CalibrationVocabulary) -> Option<u32> {
+    let mut digits = vocabulary.digits(input);
+    let first_digit = digits.next()?;
+    let last_digit = digits.last().unwrap_or(first_digit);
+    Some(first_digit * 10 + last_digit)
+}
+
+/// Sums the calibration values, reading digits with the given vocabulary.
+/// Blank lines are skipped, and any other line without a digit is reported.
+pub fn sum_calibration_values(
+    input: &str,
+    vocabulary: &CalibrationVocabulary,
+) -> CalibrationReport {
+    let lines = input.lines().collect_vec();
+    let values = lines
+        .par_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, parse_calibration_line(line, vocabulary)))
+        .collect::<Vec<_>>();
+    let digitless_lines = values
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|&(line, _)| line)
+        .collect();
+    let sum = values.iter().filter_map(|&(_, value)| value).sum();
+    CalibrationReport {
+        sum,
+        digitless_lines,
+    }
 }
 
 pub fn day01_star1(input: &str) -> u32 {
-    input
-        .par_split_whitespace()
-        .map(parse_calibration_line_digits_only)
-        .sum()
+    sum_calibration_values(input, &DIGITS_ONLY)
+        .strict()
+        .unwrap()
 }
 
 pub fn day01_star2(input: &str) -> u32 {
-    input
-        .par_split_whitespace()
-        .map(parse_calibration_line_words_and_digits)
-        .sum()
+    sum_calibration_values(input, &ENGLISH).strict().unwrap()
 }
 
 #[cfg(test)]
@@ -117,4 +239,80 @@ mod tests {
         let actual = day01_star2(&file);
         Ok(assert_eq!(actual, 55343))
     }
+
+    #[test]
+    fn day01_other_languages() {
+        let input = indoc! {"
+            deux1neuf
+            huitroisquatre
+            dreisiebenzweins
+            fünfnull
+            cuatrocincoseis"};
+        let actual = input
+            .lines()
+            .map(|line| {
+                [
+                    CalibrationVocabulary::french(),
+                    CalibrationVocabulary::german(),
+                    CalibrationVocabulary::spanish(),
+                ]
+                .iter()
+                .map(|vocabulary| vocabulary.digits(line).collect_vec())
+                .find(|digits| digits.len() > 1)
+                .unwrap()
+            })
+            .collect_vec();
+        let expected = vec![
+            vec![2, 1, 9],
+            vec![8, 3, 4],
+            vec![3, 7, 2, 1],
+            vec![5, 0],
+            vec![4, 5, 6],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn day01_vocabulary_file() {
+        let vocabulary: CalibrationVocabulary = indoc! {"
+            # roman numerals
+            i 1
+            ii 2
+            v 5
+            ix 9
+        "}
+        .parse()
+        .unwrap();
+        assert_eq!(
+            sum_calibration_values("xivx\nii3", &vocabulary).sum,
+            15 + 13
+        );
+        let actual = "one 1\nten 10".parse::<CalibrationVocabulary>();
+        assert!(matches!(
+            actual,
+            Err(VocabularyError::InvalidDigit { line: 2, .. })
+        ));
+        let actual = "one".parse::<CalibrationVocabulary>();
+        assert!(matches!(actual, Err(VocabularyError::InvalidLine(1))));
+    }
+
+    #[test]
+    fn day01_digitless_lines() {
+        let input = indoc! {"
+            1abc2
+            pqr3stu8vwx
+            no digits here
+
+            treb7uchet
+            nor nine here"};
+        let actual = sum_calibration_values(input, &DIGITS_ONLY);
+        let expected = CalibrationReport {
+            sum: 12 + 38 + 77,
+            digitless_lines: vec![3, 6],
+        };
+        assert_eq!(actual, expected);
+        assert_eq!(actual.strict(), Err(DigitlessLine(3)));
+        let actual = sum_calibration_values(input, &ENGLISH);
+        assert_eq!(actual.digitless_lines, vec![3]);
+    }
 }
@@ -1,52 +1,87 @@
 use rayon::prelude::*;
+use std::collections::BTreeMap;
 
-fn is_colour_possible(num: u32, colour: &str) -> bool {
-    match colour {
-        "red" => num <= 12,
-        "green" => num <= 13,
-        "blue" => num <= 14,
-        _ => false,
-    }
-}
+/// A count of cubes for each colour.
+pub type Cubes<'a> = BTreeMap<&'a str, u32>;
 
-fn is_set_possible(input: &str) -> bool {
-    input.split(',').all(|string| {
-        let space_split = string.trim_start().split_once(' ').unwrap();
-        let num = space_split.0.parse::<u32>().unwrap();
-        is_colour_possible(num, space_split.1)
-    })
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game<'a> {
+    pub id: u32,
+    pub rounds: Vec<Cubes<'a>>,
 }
 
-fn get_possible_game_id(input: &str) -> Option<u32> {
-    let colon_split = input.split_once(':').unwrap();
-    let game_id = colon_split.0[5..].parse::<u32>().unwrap();
-    let mut semicolon_split = colon_split.1.split(';');
-    semicolon_split.all(is_set_possible).then_some(game_id)
+fn parse_round(input: &str) -> Cubes<'_> {
+    input
+        .split(',')
+        .map(|string| {
+            let (num, colour) = string.trim().split_once(' ').unwrap();
+            (colour, num.parse().unwrap())
+        })
+        .collect()
 }
 
-fn calculate_game_power(input: &str) -> u32 {
-    let colour_sets = input.split_once(':').unwrap().1.split([';', ',']);
-    let mut rgb: (u32, u32, u32) = (0, 0, 0);
-    for colour_set in colour_sets {
-        if let Some((num, colour)) = colour_set.trim_start().split_once(' ') {
-            let n = num.parse::<u32>().unwrap();
-            match colour {
-                "red" if n > rgb.0 => rgb.0 = n,
-                "green" if n > rgb.1 => rgb.1 = n,
-                "blue" if n > rgb.2 => rgb.2 = n,
-                _ => (),
-            }
+impl<'a> Game<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let (game_str, rounds_str) = input.split_once(':').unwrap();
+        Self {
+            id: game_str[5..].parse().unwrap(),
+            rounds: rounds_str.split(';').map(parse_round).collect(),
         }
     }
-    rgb.0 * rgb.1 * rgb.2
+
+    /// The fewest cubes of each colour that could have been in the bag.
+    pub fn minimum_bag(&self) -> Cubes<'a> {
+        colour_maxima(self.rounds.iter())
+    }
+
+    /// Whether every round fits in the bag, where missing colours have no cubes.
+    pub fn is_possible_with(&self, bag: &Cubes) -> bool {
+        self.minimum_bag()
+            .iter()
+            .all(|(colour, &num)| num <= bag.get(colour).copied().unwrap_or(0))
+    }
+
+    /// The product of the minimum bag over the given colours, where a colour
+    /// that never shows up has no cubes and so makes the power 0.
+    pub fn power(&self, colours: &[&str]) -> u32 {
+        let minimum_bag = self.minimum_bag();
+        colours
+            .iter()
+            .map(|colour| minimum_bag.get(colour).copied().unwrap_or(0))
+            .product()
+    }
+}
+
+/// The most cubes of each colour shown in any one of the given rounds.
+pub fn colour_maxima<'a: 'b, 'b>(rounds: impl Iterator<Item = &'b Cubes<'a>>) -> Cubes<'a> {
+    let mut maxima = Cubes::new();
+    for (&colour, &num) in rounds.flatten() {
+        let max = maxima.entry(colour).or_default();
+        *max = num.max(*max);
+    }
+    maxima
+}
+
+pub fn parse_games(input: &str) -> Vec<Game<'_>> {
+    input.par_lines().map(Game::new).collect()
 }
 
+pub fn possible_game_ids<'a>(games: &'a [Game], bag: &'a Cubes) -> impl Iterator<Item = u32> + 'a {
+    games
+        .iter()
+        .filter(|game| game.is_possible_with(bag))
+        .map(|game| game.id)
+}
+
+const RGB: [&str; 3] = ["red", "green", "blue"];
+
 pub fn day02_star1(input: &str) -> u32 {
-    input.par_lines().filter_map(get_possible_game_id).sum()
+    let bag = Cubes::from([("red", 12), ("green", 13), ("blue", 14)]);
+    possible_game_ids(&parse_games(input), &bag).sum()
 }
 
 pub fn day02_star2(input: &str) -> u32 {
-    input.par_lines().map(calculate_game_power).sum()
+    parse_games(input).iter().map(|game| game.power(&RGB)).sum()
 }
 
 #[cfg(test)]
@@ -54,6 +89,7 @@ mod tests {
     use super::*;
     use anyhow::{Ok, Result};
     use indoc::indoc;
+    use itertools::Itertools;
     use pretty_assertions::assert_eq;
     use std::{fs::read_to_string, path::Path};
 
@@ -89,4 +125,37 @@ mod tests {
         let actual = day02_star2(&file);
         Ok(assert_eq!(actual, 63981))
     }
+
+    #[test]
+    fn day02_game_queries() {
+        let games = parse_games(EXAMPLE_INPUT);
+        assert_eq!(
+            games[0].rounds[1],
+            Cubes::from([("red", 1), ("green", 2), ("blue", 6)])
+        );
+        let expected = Cubes::from([("red", 20), ("green", 13), ("blue", 6)]);
+        assert_eq!(games[2].minimum_bag(), expected);
+        let expected = Cubes::from([("red", 20), ("green", 13), ("blue", 15)]);
+        assert_eq!(
+            colour_maxima(games.iter().flat_map(|game| &game.rounds)),
+            expected
+        );
+        let bag = Cubes::from([("red", 6), ("green", 3), ("blue", 6)]);
+        assert_eq!(possible_game_ids(&games, &bag).collect_vec(), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn day02_other_colours() {
+        let games = parse_games("Game 7: 2 teal, 1 red; 3 teal\nGame 8: 1 mauve");
+        let bag = Cubes::from([("red", 1), ("teal", 3)]);
+        assert_eq!(possible_game_ids(&games, &bag).collect_vec(), vec![7]);
+        assert_eq!(games[0].power(&["red", "teal"]), 3);
+        assert_eq!(games[0].power(&["red", "teal", "mauve"]), 0);
+    }
+
+    #[test]
+    fn day02_missing_colour_power() {
+        assert_eq!(day02_star2("Game 1: 3 red, 4 blue"), 0);
+        assert_eq!(day02_star2("Game 1: 3 red, 4 blue; 2 green"), 24);
+    }
 }
@@ -1,89 +1,121 @@
+use crate::common::grid2d::Point2D;
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartNumber {
+    pub value: u32,
+    pub row: usize,
+    pub columns: Range<usize>,
+}
+
+/// A symbol, with the indices of every number next to it, diagonals included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub character: u8,
+    pub position: Point2D,
+    pub adjacent: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schematic {
+    pub numbers: Vec<PartNumber>,
+    pub symbols: Vec<Symbol>,
+}
+
 const fn is_ascii_symbol_not_dot(char: u8) -> bool {
     char != b'.' && char.is_ascii_punctuation()
 }
 
-fn find_engine_part_sum(bytes: &[u8]) -> u32 {
-    let width = bytes.iter().position(|c| *c == b'\n').unwrap() + 1;
-    let mut part_sum: u32 = 0;
-    let mut index = 0;
-    while index < bytes.len() {
-        if is_ascii_symbol_not_dot(bytes[index]) {
-            let x = index % width;
-            let y = index / width;
-            // check adjacent spaces for numbers
-            let mut num_y = y - 1;
-            while num_y <= y + 1 {
-                let mut num_x = x - 1;
-                while num_x <= x + 1 {
-                    if bytes[num_y * width + num_x].is_ascii_digit() {
-                        // find the start of the number
-                        while num_x > 0 && bytes[num_y * width + num_x - 1].is_ascii_digit() {
-                            num_x -= 1;
-                        }
-                        let num_start = num_y * width + num_x;
-                        while bytes[num_y * width + num_x].is_ascii_digit() {
-                            num_x += 1;
-                        }
-                        let num_bytes = &bytes[num_start..(num_y * width + num_x)];
-                        part_sum += atoi::atoi::<u32>(num_bytes).unwrap();
+impl Schematic {
+    pub fn new(input: &str) -> Self {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for (y, line) in input.lines().enumerate() {
+            let bytes = line.as_bytes();
+            let mut x = 0;
+            while x < bytes.len() {
+                if bytes[x].is_ascii_digit() {
+                    let start = x;
+                    while x < bytes.len() && bytes[x].is_ascii_digit() {
+                        x += 1;
                     }
-                    num_x += 1;
+                    numbers.push(PartNumber {
+                        value: atoi::atoi(&bytes[start..x]).unwrap(),
+                        row: y,
+                        columns: start..x,
+                    });
+                    continue;
                 }
-                num_y += 1;
+                if is_ascii_symbol_not_dot(bytes[x]) {
+                    symbols.push(Symbol {
+                        character: bytes[x],
+                        position: (x, y),
+                        adjacent: Vec::new(),
+                    });
+                }
+                x += 1;
             }
         }
-        index += 1;
+
+        // Numbers are in reading order, so we only need to check the ones
+        // from the row above the symbol to the row below it
+        for symbol in &mut symbols {
+            let (x, y) = symbol.position;
+            let first = numbers.partition_point(|number| number.row + 1 < y);
+            symbol.adjacent = (first..numbers.len())
+                .take_while(|&i| numbers[i].row <= y + 1)
+                .filter(|&i| numbers[i].columns.start <= x + 1 && numbers[i].columns.end >= x)
+                .collect();
+        }
+
+        Self { numbers, symbols }
     }
-    part_sum
-}
 
-fn find_gear_ratio_sum(bytes: &[u8]) -> u32 {
-    let width = bytes.iter().position(|c| *c == b'\n').unwrap() + 1;
-    let mut ratio_sum: u32 = 0;
-    let mut index = 0;
-    while index < bytes.len() {
-        if is_ascii_symbol_not_dot(bytes[index]) {
-            let x = index % width;
-            let y = index / width;
-            let mut first_part_number = 0;
-            // check adjacent spaces for numbers
-            let mut num_y = y - 1;
-            while num_y <= y + 1 {
-                let mut num_x = x - 1;
-                while num_x <= x + 1 {
-                    if bytes[num_y * width + num_x].is_ascii_digit() {
-                        // find the start of the number
-                        while num_x > 0 && bytes[num_y * width + num_x - 1].is_ascii_digit() {
-                            num_x -= 1;
-                        }
-                        let num_start = num_y * width + num_x;
-                        while bytes[num_y * width + num_x].is_ascii_digit() {
-                            num_x += 1;
-                        }
-                        let num_bytes = &bytes[num_start..(num_y * width + num_x)];
-                        let part_number = atoi::atoi::<u32>(num_bytes).unwrap();
-                        if first_part_number == 0 {
-                            first_part_number = part_number;
-                        } else {
-                            ratio_sum += first_part_number * part_number;
-                        }
-                    }
-                    num_x += 1;
-                }
-                num_y += 1;
-            }
+    pub fn symbol_at(&self, position: Point2D) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .find(|symbol| symbol.position == position)
+    }
+
+    pub fn adjacent_numbers<'a>(
+        &'a self,
+        symbol: &'a Symbol,
+    ) -> impl Iterator<Item = &'a PartNumber> + 'a {
+        symbol.adjacent.iter().map(|&i| &self.numbers[i])
+    }
+
+    /// Every number next to at least one symbol, each counted once.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &PartNumber> {
+        let mut is_part = vec![false; self.numbers.len()];
+        for &i in self.symbols.iter().flat_map(|symbol| &symbol.adjacent) {
+            is_part[i] = true;
         }
-        index += 1;
+        self.numbers
+            .iter()
+            .zip(is_part)
+            .filter_map(|(number, is_part)| is_part.then_some(number))
+    }
+
+    /// The ratio of every `*` next to exactly two numbers.
+    pub fn gear_ratios(&self) -> impl Iterator<Item = u32> + '_ {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.character == b'*' && symbol.adjacent.len() == 2)
+            .map(|symbol| {
+                self.adjacent_numbers(symbol)
+                    .map(|number| number.value)
+                    .product()
+            })
     }
-    ratio_sum
 }
 
 pub fn day03_star1(input: &str) -> u32 {
-    find_engine_part_sum(&input.bytes().collect::<Vec<u8>>())
+    let schematic = Schematic::new(input);
+    schematic.part_numbers().map(|number| number.value).sum()
 }
 
 pub fn day03_star2(input: &str) -> u32 {
-    find_gear_ratio_sum(&input.bytes().collect::<Vec<u8>>())
+    Schematic::new(input).gear_ratios().sum()
 }
 
 #[cfg(test)]
@@ -91,6 +123,7 @@ mod tests {
     use super::*;
     use anyhow::{Ok, Result};
     use indoc::indoc;
+    use itertools::Itertools;
     use pretty_assertions::assert_eq;
     use std::{fs::read_to_string, path::Path};
 
@@ -131,4 +164,42 @@ mod tests {
         let actual = day03_star2(&file);
         Ok(assert_eq!(actual, 84_363_105))
     }
+
+    #[test]
+    fn day03_schematic_index() {
+        let schematic = Schematic::new(EXAMPLE_INPUT);
+        let expected = PartNumber {
+            value: 633,
+            row: 2,
+            columns: 6..9,
+        };
+        assert_eq!(schematic.numbers[3], expected);
+        let symbol = schematic.symbol_at((5, 8)).unwrap();
+        let actual = schematic
+            .adjacent_numbers(symbol)
+            .map(|n| n.value)
+            .collect_vec();
+        assert_eq!(actual, vec![755, 598]);
+        assert_eq!(schematic.symbol_at((0, 0)), None);
+        let actual = schematic.part_numbers().count();
+        assert_eq!(actual, schematic.numbers.len() - 2);
+    }
+
+    #[test]
+    fn day03_edges_and_gears() {
+        // symbols in the corners, a number next to two symbols and a three-number `*`
+        let input = indoc! {"
+            *2.3
+            ....
+            4*5.
+            .6.#"};
+        let schematic = Schematic::new(input);
+        assert_eq!(
+            schematic.part_numbers().map(|n| n.value).sum::<u32>(),
+            2 + 4 + 5 + 6
+        );
+        assert_eq!(schematic.gear_ratios().collect_vec(), Vec::<u32>::new());
+        let schematic = Schematic::new("1*2*3");
+        assert_eq!(schematic.gear_ratios().collect_vec(), vec![2, 6]);
+    }
 }
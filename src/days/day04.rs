@@ -1,48 +1,123 @@
-use crate::common::parsing::parse_u32_set;
+use crate::common::parsing::parse_u32_vec;
+use itertools::Itertools;
 use rayon::prelude::*;
-use std::collections::HashSet;
 
-fn parse_number_sets(input: &str) -> (HashSet<u32>, HashSet<u32>) {
-    let mut parts = input.split([':', '|']);
-    let winning_numbers = parse_u32_set(parts.nth(1).unwrap());
-    let card_numbers = parse_u32_set(parts.next().unwrap());
-    (winning_numbers, card_numbers)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scratchcard {
+    pub id: usize,
+    pub winning: Vec<u32>,
+    pub held: Vec<u32>,
+    pub matches: usize,
 }
 
-fn calculate_card_score(input: &str) -> u32 {
-    let (winning_numbers, card_numbers) = parse_number_sets(input);
-    card_numbers
-        .into_iter()
-        .filter(|x| winning_numbers.contains(x))
-        .fold(0, |acc, _| match acc {
-            0 => 1,
-            _ => acc * 2,
-        })
+impl Scratchcard {
+    pub fn new(input: &str) -> Self {
+        let (id_str, numbers_str) = input.split_once(':').unwrap();
+        let (winning_str, held_str) = numbers_str.split_once('|').unwrap();
+        let winning = parse_u32_vec(winning_str);
+        let held = parse_u32_vec(held_str);
+        Self {
+            id: id_str[4..].trim().parse().unwrap(),
+            // A number held twice still only matches once
+            matches: held.iter().unique().filter(|x| winning.contains(x)).count(),
+            winning,
+            held,
+        }
+    }
+
+    pub const fn score(&self) -> u32 {
+        match self.matches {
+            0 => 0,
+            matches => 1 << (matches - 1),
+        }
+    }
 }
 
-fn count_win_amount(input: &str) -> usize {
-    let (winning_numbers, card_numbers) = parse_number_sets(input);
-    card_numbers
-        .into_iter()
-        .filter(|x| winning_numbers.contains(x))
-        .count()
+/// How a card came to be held, and which cards its copies went on to win.
+/// Matches that would win copies of cards past the end of the table are
+/// counted in `past_last_card` instead of being dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CascadeEntry {
+    pub id: usize,
+    pub matches: usize,
+    pub copies: u32,
+    pub generated: Vec<usize>,
+    pub past_last_card: usize,
 }
 
-pub fn day04_star1(input: &str) -> u32 {
-    input.par_lines().map(calculate_card_score).sum()
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CascadeReport {
+    pub entries: Vec<CascadeEntry>,
 }
 
-pub fn day04_star2(input: &str) -> u32 {
-    let cards: Vec<&str> = input.lines().collect();
-    let mut card_amounts: Vec<u32> = vec![1; cards.len()];
-    for (i, card) in cards.iter().enumerate() {
-        let win_amount = count_win_amount(card);
-        let added_amount = card_amounts[i];
-        for card_amount in card_amounts.iter_mut().take(i + win_amount + 1).skip(i + 1) {
-            *card_amount += added_amount;
+impl CascadeReport {
+    pub fn new(cards: &[Scratchcard]) -> Self {
+        let mut copies = vec![1; cards.len()];
+        let mut entries = Vec::with_capacity(cards.len());
+        for (i, card) in cards.iter().enumerate() {
+            let won = (i + 1..=i + card.matches).filter(|&j| j < cards.len());
+            for j in won.clone() {
+                copies[j] += copies[i];
+            }
+            let generated = won.map(|j| cards[j].id).collect_vec();
+            entries.push(CascadeEntry {
+                id: card.id,
+                matches: card.matches,
+                copies: copies[i],
+                past_last_card: card.matches - generated.len(),
+                generated,
+            });
         }
+        Self { entries }
     }
-    card_amounts.into_iter().sum()
+
+    pub fn total_cards(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.copies).sum()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let header = "id,matches,copies,generated,past_last_card\n";
+        let rows = self.entries.iter().map(|entry| {
+            format!(
+                "{},{},{},{},{}\n",
+                entry.id,
+                entry.matches,
+                entry.copies,
+                entry.generated.iter().join(" "),
+                entry.past_last_card
+            )
+        });
+        std::iter::once(header.to_owned()).chain(rows).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries = self.entries.iter().map(|entry| {
+            format!(
+                "{{\"id\":{},\"matches\":{},\"copies\":{},\"generated\":[{}],\"past_last_card\":{}}}",
+                entry.id,
+                entry.matches,
+                entry.copies,
+                entry.generated.iter().join(","),
+                entry.past_last_card
+            )
+        });
+        format!("[{}]", entries.format(","))
+    }
+}
+
+pub fn parse_scratchcards(input: &str) -> Vec<Scratchcard> {
+    input.par_lines().map(Scratchcard::new).collect()
+}
+
+pub fn day04_star1(input: &str) -> u32 {
+    parse_scratchcards(input)
+        .iter()
+        .map(Scratchcard::score)
+        .sum()
+}
+
+pub fn day04_star2(input: &str) -> u32 {
+    CascadeReport::new(&parse_scratchcards(input)).total_cards()
 }
 
 #[cfg(test)]
@@ -86,4 +161,51 @@ mod tests {
         let actual = day04_star2(&file);
         Ok(assert_eq!(actual, 8_549_735))
     }
+
+    #[test]
+    fn day04_cascade_report() {
+        let report = CascadeReport::new(&parse_scratchcards(EXAMPLE_INPUT));
+        let expected = CascadeEntry {
+            id: 3,
+            matches: 2,
+            copies: 4,
+            generated: vec![4, 5],
+            past_last_card: 0,
+        };
+        assert_eq!(report.entries[2], expected);
+        let actual = report.to_csv();
+        let expected = indoc! {"
+            id,matches,copies,generated,past_last_card
+            1,4,1,2 3 4 5,0
+            2,2,2,3 4,0
+            3,2,4,4 5,0
+            4,1,8,5,0
+            5,0,14,,0
+            6,0,1,,0
+        "};
+        assert_eq!(actual, expected);
+        let actual = report.to_json();
+        assert!(actual.starts_with(
+            r#"[{"id":1,"matches":4,"copies":1,"generated":[2,3,4,5],"past_last_card":0},"#
+        ));
+    }
+
+    #[test]
+    fn day04_matches_past_last_card() {
+        let input = indoc! {"
+            Card 1: 1 2 | 1 3
+            Card 2: 1 2 3 | 1 2 3"};
+        let report = CascadeReport::new(&parse_scratchcards(input));
+        assert_eq!(report.entries[1].generated, Vec::<usize>::new());
+        assert_eq!(report.entries[1].past_last_card, 3);
+        assert_eq!(report.total_cards(), 3);
+    }
+
+    #[test]
+    fn day04_duplicate_held_numbers() {
+        let card = Scratchcard::new("Card 1: 5 | 5 5");
+        assert_eq!(card.held, vec![5, 5]);
+        assert_eq!(card.matches, 1);
+        assert_eq!(card.score(), 1);
+    }
 }
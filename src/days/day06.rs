@@ -1,25 +1,54 @@
-use crate::common::parsing::{parse_f64_iter, parse_f64_with_spaces};
+use crate::common::parsing::{parse_u128_vec, parse_u64_vec, parse_with_spaces};
 
-fn calculate_winning_amount((time, distance): (f64, f64)) -> u64 {
-    // calculate the difference between the zeroes of the quadratic equation:
+const fn beats_record(hold: u128, time: u128, distance: u128) -> bool {
+    hold * (time - hold) > distance
+}
+
+fn calculate_winning_amount((time, distance): (u64, u128)) -> u64 {
+    // the winning holds lie strictly between the zeroes of the quadratic equation:
     // x^2 - time * x + distance = 0
-    let disc_sqrt = time.mul_add(time, -(4.0 * distance)).sqrt();
-    let root1 = ((time - disc_sqrt) / 2.0).floor();
-    let root2 = ((time + disc_sqrt) / 2.0).ceil();
-    (root2 - root1) as u64 - 1
+    let time = u128::from(time);
+    // a record too big to quadruple is far beyond anything a u64 time can reach
+    let Some(disc) = distance
+        .checked_mul(4)
+        .and_then(|four_distance| (time * time).checked_sub(four_distance))
+    else {
+        return 0;
+    };
+
+    // the integer square root gets us within one of the lower zero, so nudge it
+    // onto the first winning hold (this also covers zeroes that are integers)
+    let half = time / 2;
+    let mut lowest = (time - disc.isqrt()) / 2;
+    while lowest > 0 && beats_record(lowest - 1, time, distance) {
+        lowest -= 1;
+    }
+    while lowest <= half && !beats_record(lowest, time, distance) {
+        lowest += 1;
+    }
+    if lowest > half {
+        return 0;
+    }
+
+    // winning holds are symmetric around time / 2
+    (time - 2 * lowest + 1) as u64
 }
 
 pub fn day06_star1(input: &str) -> u64 {
     let mut lines = input.lines();
-    let times = parse_f64_iter(&lines.next().unwrap()[11..]);
-    let distances = parse_f64_iter(&lines.next().unwrap()[11..]);
-    times.zip(distances).map(calculate_winning_amount).product()
+    let times = parse_u64_vec(&lines.next().unwrap()[11..]);
+    let distances = parse_u128_vec(&lines.next().unwrap()[11..]);
+    times
+        .into_iter()
+        .zip(distances)
+        .map(calculate_winning_amount)
+        .product()
 }
 
 pub fn day06_star2(input: &str) -> u64 {
     let mut lines = input.lines();
-    let time = parse_f64_with_spaces(&lines.next().unwrap()[11..]);
-    let distance = parse_f64_with_spaces(&lines.next().unwrap()[11..]);
+    let time = parse_with_spaces(&lines.next().unwrap()[11..]);
+    let distance = parse_with_spaces(&lines.next().unwrap()[11..]);
     calculate_winning_amount((time, distance))
 }
 
@@ -60,4 +89,48 @@ mod tests {
         let actual = day06_star2(&file);
         Ok(assert_eq!(actual, 34_934_171))
     }
+
+    #[test]
+    fn day06_exact_integer_roots() {
+        // 10 * 20 == 200, so holding for 10 or 20 only ties the record
+        assert_eq!(calculate_winning_amount((30, 200)), 9);
+        assert_eq!(calculate_winning_amount((30, 199)), 11);
+        // the best possible hold only ties, so nothing wins
+        assert_eq!(calculate_winning_amount((30, 225)), 0);
+        assert_eq!(calculate_winning_amount((30, 1000)), 0);
+    }
+
+    #[test]
+    fn day06_past_f64_precision() {
+        // 2^53 + 1 can't be represented as an f64
+        let time: u64 = (1 << 53) + 1;
+        let hold = 123_456_789_u128;
+        let record = hold * (u128::from(time) - hold);
+        let expected = time - 2 * 123_456_789 - 1;
+        assert_eq!(calculate_winning_amount((time, record)), expected);
+        assert_eq!(calculate_winning_amount((time, record - 1)), expected + 2);
+    }
+
+    #[test]
+    fn day06_star2_past_f64_precision() {
+        let input = indoc! {"
+            Time:      18446744  073709551 615
+            Distance:  85070591730234615856620279821087277056"};
+        // the time is 2^64 - 1 and the record is the best possible distance,
+        // reached by the two holds either side of the middle
+        let actual = day06_star2(input);
+        assert_eq!(actual, 0);
+        let input = indoc! {"
+            Time:      18446744  073709551 615
+            Distance:  85070591730234615856620279821087277055"};
+        let actual = day06_star2(input);
+        assert_eq!(actual, 2);
+        // records of 2^126 and above overflow when multiplied by 4
+        let input = indoc! {"
+            Time:      10
+            Distance:  100000000000000000000000000000000000000"};
+        let actual = day06_star2(input);
+        assert_eq!(actual, 0);
+        assert_eq!(calculate_winning_amount((u64::MAX, u128::MAX)), 0);
+    }
 }
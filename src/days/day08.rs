@@ -1,4 +1,33 @@
-use std::collections::HashMap;
+use crate::common::maths::combine_congruences;
+use itertools::Itertools;
+use num::{BigInt, Integer};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Write},
+};
+
+/// The path of a single ghost through the `(node, instruction index)` state graph.
+/// Steps are counted from the ghost's starting node, so the ghost repeats the
+/// states it was in at `prefix_length..(prefix_length + cycle_length)` forever.
+#[derive(Debug, PartialEq, Eq)]
+struct GhostCycle {
+    pub prefix_length: u64,
+    pub cycle_length: u64,
+    pub prefix_end_steps: Vec<u64>,
+    pub cycle_end_steps: Vec<u64>,
+}
+
+impl GhostCycle {
+    fn is_end_step(&self, step: u64) -> bool {
+        if step < self.prefix_length {
+            self.prefix_end_steps.contains(&step)
+        } else {
+            let offset = (step - self.prefix_length) % self.cycle_length;
+            self.cycle_end_steps
+                .contains(&(self.prefix_length + offset))
+        }
+    }
+}
 
 fn parse_input(input: &str) -> (&str, HashMap<&str, (&str, &str)>) {
     let mut lines = input.lines();
@@ -23,6 +52,149 @@ fn count_steps(choices: &str, nodes: &HashMap<&str, (&str, &str)>, starting_node
     steps as u64
 }
 
+fn analyse_ghost(
+    choices: &str,
+    nodes: &HashMap<&str, (&str, &str)>,
+    starting_node: &str,
+) -> GhostCycle {
+    let choices = choices.as_bytes();
+    let mut first_seen = HashMap::new();
+    let mut end_steps = Vec::new();
+    let mut current_node = starting_node;
+    let mut step: u64 = 0;
+    let (prefix_length, cycle_length) = loop {
+        let choice_index = (step % choices.len() as u64) as usize;
+        if let Some(&first_step) = first_seen.get(&(current_node, choice_index)) {
+            break (first_step, step - first_step);
+        }
+        first_seen.insert((current_node, choice_index), step);
+        if current_node.ends_with('Z') {
+            end_steps.push(step);
+        }
+        let options = nodes[current_node];
+        current_node = if choices[choice_index] == b'L' {
+            options.0
+        } else {
+            options.1
+        };
+        step += 1;
+    };
+    let (prefix_end_steps, cycle_end_steps) = end_steps
+        .into_iter()
+        .partition(|&end_step| end_step < prefix_length);
+    GhostCycle {
+        prefix_length,
+        cycle_length,
+        prefix_end_steps,
+        cycle_end_steps,
+    }
+}
+
+fn find_synchronised_step(ghosts: &[GhostCycle]) -> Option<BigInt> {
+    // Any step inside a ghost's prefix is earlier than every step where all ghosts are cycling
+    let prefix_step = ghosts
+        .iter()
+        .flat_map(|ghost| ghost.prefix_end_steps.iter().copied())
+        .filter(|&step| ghosts.iter().all(|ghost| ghost.is_end_step(step)))
+        .min();
+    if let Some(step) = prefix_step {
+        return Some(BigInt::from(step));
+    }
+
+    // Otherwise, combine every possible end step of each cycle with the CRT,
+    // using big integers as the combined modulus is the LCM of every cycle length
+    let mut congruences = vec![(BigInt::from(0), BigInt::from(1))];
+    for ghost in ghosts {
+        let modulus = BigInt::from(ghost.cycle_length);
+        congruences = congruences
+            .into_iter()
+            .cartesian_product(&ghost.cycle_end_steps)
+            .filter_map(|(congruence, &step)| {
+                let residue = BigInt::from(step).mod_floor(&modulus);
+                combine_congruences(congruence, (residue, modulus.clone()))
+            })
+            .unique()
+            .collect();
+    }
+
+    // Each solution only holds once every ghost has finished its prefix
+    let threshold = BigInt::from(ghosts.iter().map(|ghost| ghost.prefix_length).max()?);
+    congruences
+        .into_iter()
+        .map(|(residue, modulus)| &threshold + (residue - &threshold).mod_floor(&modulus))
+        .min()
+}
+
+fn walk_path<'a>(
+    choices: &str,
+    nodes: &HashMap<&'a str, (&'a str, &'a str)>,
+    starting_node: &'a str,
+) -> HashSet<(&'a str, u8)> {
+    let mut walked = HashSet::new();
+    let mut seen_states = HashSet::new();
+    let mut current_node = starting_node;
+    // Stop at the first ..Z node, or as soon as the walk starts repeating itself
+    for (choice_index, choice) in choices.bytes().enumerate().cycle() {
+        if current_node.ends_with('Z') || !seen_states.insert((current_node, choice_index)) {
+            break;
+        }
+        walked.insert((current_node, choice));
+        let Some(&options) = nodes.get(current_node) else {
+            // An edge to a node that isn't in the network
+            break;
+        };
+        current_node = if choice == b'L' { options.0 } else { options.1 };
+    }
+    walked
+}
+
+/// A node that was asked for but isn't in the network.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownNode(pub String);
+
+impl fmt::Display for UnknownNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "there's no node {:?} in the network", self.0)
+    }
+}
+
+impl std::error::Error for UnknownNode {}
+
+pub fn network_to_dot(input: &str, highlight_from: Option<&str>) -> Result<String, UnknownNode> {
+    let (choices, nodes) = parse_input(input);
+    let walked = match highlight_from {
+        Some(start) if !nodes.contains_key(start) => return Err(UnknownNode(start.to_owned())),
+        Some(start) => walk_path(choices, &nodes, start),
+        None => HashSet::new(),
+    };
+
+    let mut dot = String::from("digraph network {\n");
+    for &node in nodes.keys().sorted() {
+        if node.ends_with('A') {
+            writeln!(dot, "    \"{node}\" [style=filled, fillcolor=palegreen];").unwrap();
+        } else if node.ends_with('Z') {
+            writeln!(dot, "    \"{node}\" [style=filled, fillcolor=lightcoral];").unwrap();
+        }
+    }
+    for (&node, &(left, right)) in nodes.iter().sorted_by_key(|&(&node, _)| node) {
+        for (choice, next_node) in [(b'L', left), (b'R', right)] {
+            let style = if walked.contains(&(node, choice)) {
+                ", color=blue, penwidth=3"
+            } else {
+                ""
+            };
+            let label = choice as char;
+            writeln!(
+                dot,
+                "    \"{node}\" -> \"{next_node}\" [label=\"{label}\"{style}];"
+            )
+            .unwrap();
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
 pub fn day08_star1(input: &str) -> u64 {
     let (choices, nodes) = parse_input(input);
     count_steps(choices, &nodes, "AAA")
@@ -30,12 +202,13 @@ pub fn day08_star1(input: &str) -> u64 {
 
 pub fn day08_star2(input: &str) -> u64 {
     let (choices, nodes) = parse_input(input);
-    nodes
+    let ghosts = nodes
         .keys()
         .filter(|&&node| node.ends_with('A'))
-        .map(|&node| count_steps(choices, &nodes, node))
-        .reduce(num::integer::lcm)
-        .unwrap()
+        .map(|&node| analyse_ghost(choices, &nodes, node))
+        .collect_vec();
+    let step = find_synchronised_step(&ghosts).expect("ghosts never all reach a ..Z node together");
+    u64::try_from(step).expect("the synchronised step doesn't fit in a u64")
 }
 
 #[cfg(test)]
@@ -111,4 +284,138 @@ mod tests {
         let actual = day08_star2(&file);
         Ok(assert_eq!(actual, 7_309_459_565_207))
     }
+
+    #[test]
+    fn day08_analyse_ghost() {
+        let (choices, nodes) = parse_input(indoc! {"
+            LR
+
+            22A = (22B, XXX)
+            22B = (22C, 22C)
+            22C = (22Z, 22Z)
+            22Z = (22B, 22B)"});
+        let actual = analyse_ghost(choices, &nodes, "22A");
+        let expected = GhostCycle {
+            prefix_length: 1,
+            cycle_length: 6,
+            prefix_end_steps: vec![],
+            cycle_end_steps: vec![3, 6],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn day08_star2_offset_cycles() {
+        // 11A reaches 11Z after 1 step and every 3 steps after that, while 22A
+        // reaches 22Z every 2 steps, so the LCM of first arrivals (2) is wrong
+        let input = indoc! {"
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11B, 11B)
+            11B = (11C, 11C)
+            11C = (11Z, 11Z)
+            22A = (22B, 22B)
+            22B = (22Z, 22Z)
+            22Z = (22B, 22B)"};
+        let actual = day08_star2(input);
+        assert_eq!(actual, 4);
+    }
+
+    #[test]
+    fn day08_synchronised_step_in_prefix() {
+        let (choices, nodes) = parse_input(indoc! {"
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11B, 11B)
+            11B = (11B, 11B)
+            22A = (22Z, 22Z)
+            22Z = (22Z, 22Z)
+            33A = (33B, 33B)
+            33B = (33Z, 33Z)
+            33Z = (33Z, 33Z)"});
+        let ghost = |start| analyse_ghost(choices, &nodes, start);
+        assert_eq!(
+            find_synchronised_step(&[ghost("11A"), ghost("22A")]),
+            Some(BigInt::from(1))
+        );
+        assert_eq!(find_synchronised_step(&[ghost("11A"), ghost("33A")]), None);
+    }
+
+    #[test]
+    fn day08_no_synchronised_step() {
+        // 11A only reaches 11Z on odd steps, and 22A only reaches 22Z on even steps
+        let (choices, nodes) = parse_input(indoc! {"
+            L
+
+            11A = (11Z, 11Z)
+            11Z = (11B, 11B)
+            11B = (11Z, 11Z)
+            22A = (22B, 22B)
+            22B = (22Z, 22Z)
+            22Z = (22B, 22B)"});
+        let ghosts = [
+            analyse_ghost(choices, &nodes, "11A"),
+            analyse_ghost(choices, &nodes, "22A"),
+        ];
+        assert_eq!(find_synchronised_step(&ghosts), None);
+    }
+
+    #[test]
+    fn day08_many_coprime_cycles() {
+        // ten prime cycle lengths around 2^17, whose product is far past i128
+        let primes = [
+            131_071, 131_063, 131_059, 131_041, 131_023, 131_011, 131_009, 130_987, 130_981,
+            130_973,
+        ];
+        let ghosts = primes
+            .iter()
+            .map(|&cycle_length| GhostCycle {
+                prefix_length: 1,
+                cycle_length,
+                prefix_end_steps: vec![],
+                cycle_end_steps: vec![cycle_length],
+            })
+            .collect_vec();
+        let expected = primes.iter().map(|&prime| BigInt::from(prime)).product();
+        assert_eq!(find_synchronised_step(&ghosts), Some(expected));
+    }
+
+    #[test]
+    fn day08_network_to_dot() {
+        let input = indoc! {"
+            LLR
+
+            AAA = (BBB, BBB)
+            BBB = (AAA, ZZZ)
+            ZZZ = (ZZZ, ZZZ)"};
+        let expected = indoc! {r#"
+            digraph network {
+                "AAA" [style=filled, fillcolor=palegreen];
+                "ZZZ" [style=filled, fillcolor=lightcoral];
+                "AAA" -> "BBB" [label="L", color=blue, penwidth=3];
+                "AAA" -> "BBB" [label="R", color=blue, penwidth=3];
+                "BBB" -> "AAA" [label="L", color=blue, penwidth=3];
+                "BBB" -> "ZZZ" [label="R", color=blue, penwidth=3];
+                "ZZZ" -> "ZZZ" [label="L"];
+                "ZZZ" -> "ZZZ" [label="R"];
+            }
+        "#};
+        let actual = network_to_dot(input, Some("AAA")).unwrap();
+        assert_eq!(actual, expected);
+        let actual = network_to_dot(input, Some("QQQ"));
+        assert_eq!(actual, Err(UnknownNode("QQQ".to_owned())));
+    }
+
+    #[test]
+    fn day08_dangling_edge() {
+        let input = indoc! {"
+            L
+
+            AAA = (QQQ, ZZZ)
+            ZZZ = (ZZZ, ZZZ)"};
+        let actual = network_to_dot(input, Some("AAA")).unwrap();
+        assert!(actual.contains(r#""AAA" -> "QQQ" [label="L", color=blue, penwidth=3];"#));
+    }
 }
@@ -1,38 +1,60 @@
-use crate::common::parsing::parse_i64_vec;
-use itertools::Itertools;
+use crate::common::{
+    difference_table::{DifferenceTable, SequenceError},
+    parsing::{parse_bigint_vec, parse_i64_vec},
+};
+use num::{BigInt, CheckedAdd, CheckedSub};
 use rayon::prelude::*;
 
-fn calculate_next_in_sequence(sequence: &[i64]) -> i64 {
-    let differences: Vec<i64> = sequence.iter().map_windows(|[&a, &b]| b - a).collect();
-    if differences.iter().all_equal() {
-        sequence.last().unwrap() + differences[0]
+fn extrapolate<N>(sequence: &[N], forwards: bool) -> Result<N, SequenceError>
+where
+    N: Clone + PartialEq + CheckedAdd + CheckedSub + Into<BigInt>,
+{
+    let table = DifferenceTable::new(sequence)?;
+    let mut values = if forwards {
+        table.extrapolate_forwards(1)?
     } else {
-        sequence.last().unwrap() + calculate_next_in_sequence(&differences)
-    }
+        table.extrapolate_backwards(1)?
+    };
+    Ok(values.remove(0))
 }
 
-fn calculate_prev_in_sequence(sequence: &[i64]) -> i64 {
-    let differences: Vec<i64> = sequence.iter().map_windows(|[&a, &b]| b - a).collect();
-    if differences.iter().all_equal() {
-        sequence.first().unwrap() - differences[0]
-    } else {
-        sequence.first().unwrap() - calculate_prev_in_sequence(&differences)
+fn extrapolate_checked(input: &str, forwards: bool) -> BigInt {
+    // Stick to i64 where we can, only falling back to big integers when it overflows
+    let parsed: Result<Vec<i64>, _> = input.split_ascii_whitespace().map(str::parse).collect();
+    if let Ok(sequence) = parsed {
+        match extrapolate(&sequence, forwards) {
+            Err(SequenceError::Overflow) => (),
+            result => return result.unwrap().into(),
+        }
     }
+    extrapolate(&parse_bigint_vec(input), forwards).unwrap()
 }
 
 pub fn day09_star1(input: &str) -> i64 {
     input
         .par_lines()
-        .map(parse_i64_vec)
-        .map(|seq| calculate_next_in_sequence(&seq))
+        .map(|line| extrapolate(&parse_i64_vec(line), true).unwrap())
         .sum()
 }
 
 pub fn day09_star2(input: &str) -> i64 {
     input
         .par_lines()
-        .map(parse_i64_vec)
-        .map(|seq| calculate_prev_in_sequence(&seq))
+        .map(|line| extrapolate(&parse_i64_vec(line), false).unwrap())
+        .sum()
+}
+
+pub fn day09_star1_checked(input: &str) -> BigInt {
+    input
+        .par_lines()
+        .map(|line| extrapolate_checked(line, true))
+        .sum()
+}
+
+pub fn day09_star2_checked(input: &str) -> BigInt {
+    input
+        .par_lines()
+        .map(|line| extrapolate_checked(line, false))
         .sum()
 }
 
@@ -41,6 +63,8 @@ mod tests {
     use super::*;
     use anyhow::{Ok, Result};
     use indoc::indoc;
+    use itertools::Itertools;
+    use num::{BigRational, Zero};
     use pretty_assertions::assert_eq;
     use std::{fs::read_to_string, path::Path};
 
@@ -74,4 +98,64 @@ mod tests {
         let actual = day09_star2(&file);
         Ok(assert_eq!(actual, 990))
     }
+
+    fn evaluate(coefficients: &[BigInt], x: i64) -> BigInt {
+        let x = BigInt::from(x);
+        coefficients
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |acc, coefficient| acc * &x + coefficient)
+    }
+
+    #[test]
+    fn day09_checked_differences_overflow() {
+        // the values all fit in an i64, but the first differences don't
+        let input =
+            "-4700000000000000000 4700000000000000000 4700000000000000000 -4700000000000000000";
+        let expected: BigInt = "-23500000000000000000".parse().unwrap();
+        assert_eq!(day09_star1_checked(input), expected);
+        assert_eq!(day09_star2_checked(input), expected);
+    }
+
+    #[test]
+    fn day09_checked_high_degree_polynomials() {
+        for degree in [8, 15, 20] {
+            // large alternating coefficients, so the values go well past i64
+            let coefficients = (0..=degree)
+                .map(|k| BigInt::from((k + 1) * 1_000_000_007) * if k % 2 == 0 { 1 } else { -1 })
+                .collect_vec();
+            let input = (0..=degree + 3)
+                .map(|x| evaluate(&coefficients, x).to_string())
+                .join(" ");
+            assert_eq!(
+                day09_star1_checked(&input),
+                evaluate(&coefficients, degree + 4)
+            );
+            assert_eq!(day09_star2_checked(&input), evaluate(&coefficients, -1));
+
+            let sequence = parse_bigint_vec(&input);
+            let table = DifferenceTable::new(&sequence).unwrap();
+            assert_eq!(table.degree(), degree as usize);
+            let fitted = table.coefficients();
+            assert!(fitted.iter().all(BigRational::is_integer));
+            assert_eq!(
+                fitted.into_iter().map(|c| c.to_integer()).collect_vec(),
+                coefficients
+            );
+        }
+    }
+
+    #[test]
+    fn day09_checked_final_answers() -> Result<()> {
+        let file = read_to_string(Path::new("inputs/day09.txt"))?;
+        assert_eq!(day09_star1_checked(&file), BigInt::from(1_887_980_197));
+        Ok(assert_eq!(day09_star2_checked(&file), BigInt::from(990)))
+    }
+
+    #[test]
+    fn day09_short_sequences() {
+        assert_eq!(day09_star1("1 3"), 5);
+        assert_eq!(day09_star1("1 2 4"), 7);
+        assert_eq!(day09_star2("1 2 4"), 1);
+    }
 }
@@ -1,108 +1,158 @@
-use crate::common::direction::Direction;
+use crate::common::{
+    direction::Direction,
+    grid2d::{Grid2D, Point2D},
+    polygon::Polygon,
+};
 use itertools::Itertools;
+use std::{collections::HashSet, fmt};
 
-const NORTH_CONNECTIONS: [u8; 3] = [b'|', b'7', b'F'];
-const EAST_CONNECTIONS: [u8; 3] = [b'-', b'7', b'J'];
-const SOUTH_CONNECTIONS: [u8; 3] = [b'|', b'L', b'J'];
-const WEST_CONNECTIONS: [u8; 3] = [b'-', b'L', b'F'];
-
-fn trace_path(bytes: &[u8], width: usize) -> (usize, Vec<usize>) {
-    let len = bytes.len();
+#[derive(Debug, PartialEq, Eq)]
+pub enum LoopError {
+    MissingStart,
+    StartConnections(Vec<Direction>),
+    EdgeExit(Point2D, Direction),
+    DeadEnd(Point2D),
+    OpenPath(Point2D),
+}
 
-    // Find starting position and one connection to begin following
-    let (start, _) = bytes.iter().find_position(|&&b| b == b'S').unwrap();
-    let start_dir = Direction::NWSE.into_iter().find(|&dir| match dir {
-        Direction::North => {
-            start > width && NORTH_CONNECTIONS.iter().any(|&p| p == bytes[start - width])
-        }
-        Direction::East => start < len && EAST_CONNECTIONS.iter().any(|&p| p == bytes[start + 1]),
-        Direction::South => {
-            start < len - width + 1 && SOUTH_CONNECTIONS.iter().any(|&p| p == bytes[start + width])
-        }
-        Direction::West => start > 0 && WEST_CONNECTIONS.iter().any(|&p| p == bytes[start - 1]),
-    });
-
-    // Start following this path until we reach the start again!
-    let mut steps = 0;
-    let mut index = start;
-    let mut direction = start_dir.unwrap();
-    let mut path = vec![index];
-    loop {
-        steps += 1;
-        match direction {
-            Direction::North => {
-                index -= width;
-                direction = match bytes[index] {
-                    b'|' => Direction::North,
-                    b'7' => Direction::West,
-                    b'F' => Direction::East,
-                    _ => break,
-                };
-            }
-            Direction::East => {
-                index += 1;
-                direction = match bytes[index] {
-                    b'-' => Direction::East,
-                    b'7' => Direction::South,
-                    b'J' => Direction::North,
-                    _ => break,
-                };
-            }
-            Direction::South => {
-                index += width;
-                direction = match bytes[index] {
-                    b'|' => Direction::South,
-                    b'L' => Direction::East,
-                    b'J' => Direction::West,
-                    _ => break,
-                };
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingStart => write!(f, "there is no S tile in the grid"),
+            Self::StartConnections(connected) => write!(
+                f,
+                "S needs exactly two connecting neighbours, but found {}: {connected:?}",
+                connected.len()
+            ),
+            Self::EdgeExit(point, dir) => {
+                write!(
+                    f,
+                    "the pipe at {point:?} leads {dir:?} off the edge of the grid"
+                )
             }
-            Direction::West => {
-                index -= 1;
-                direction = match bytes[index] {
-                    b'-' => Direction::West,
-                    b'L' => Direction::North,
-                    b'F' => Direction::South,
-                    _ => break,
-                };
+            Self::DeadEnd(point) => write!(f, "the path runs into a non-pipe tile at {point:?}"),
+            Self::OpenPath(point) => {
+                write!(
+                    f,
+                    "the path runs into a pipe that doesn't connect back at {point:?}"
+                )
             }
         }
-        path.push(index);
     }
+}
+
+impl std::error::Error for LoopError {}
 
-    (steps, path)
+const PIPES: [(u8, [Direction; 2]); 6] = [
+    (b'|', [Direction::North, Direction::South]),
+    (b'-', [Direction::East, Direction::West]),
+    (b'L', [Direction::North, Direction::East]),
+    (b'J', [Direction::North, Direction::West]),
+    (b'7', [Direction::South, Direction::West]),
+    (b'F', [Direction::East, Direction::South]),
+];
+
+fn pipe_connections(pipe: u8) -> Option<[Direction; 2]> {
+    PIPES
+        .into_iter()
+        .find_map(|(p, connections)| (p == pipe).then_some(connections))
 }
 
-pub fn day10_star1(input: &str) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let string = input.lines().join("");
-    let bytes = string.as_bytes();
-    let (steps, _) = trace_path(bytes, width);
-    steps / 2
+fn infer_start_pipe(grid: &Grid2D, start: Point2D) -> Result<u8, LoopError> {
+    // Find every neighbour with a pipe leading back into the start
+    let connected = Direction::NWSE
+        .into_iter()
+        .filter(|&dir| {
+            grid.move_from_point(start, dir)
+                .and_then(|point| pipe_connections(grid[point]))
+                .is_some_and(|connections| connections.contains(&dir.opposite()))
+        })
+        .collect_vec();
+    PIPES
+        .into_iter()
+        .find(|(_, connections)| {
+            connected.len() == 2 && connections.iter().all(|dir| connected.contains(dir))
+        })
+        .map(|(pipe, _)| pipe)
+        .ok_or(LoopError::StartConnections(connected))
 }
 
-pub fn day10_star2(input: &str) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let string = input.lines().join("");
-    let bytes = string.as_bytes();
-    let (_, path) = trace_path(bytes, width);
-
-    let mut squares_inside = 0;
-    let mut inside = false;
-    for (index, byte) in bytes.iter().enumerate() {
-        if index % width == 0 {
-            inside = false;
-        }
-        let on_path = path.contains(&index);
-        // Flip on starting point, vertical lines, and the start of S-bends
-        if [b'S', b'|', b'F', b'7'].contains(byte) && on_path {
-            inside = !inside;
+/// Replaces the S tile with the pipe hiding under it, returning its position.
+fn replace_start(grid: &mut Grid2D) -> Result<Point2D, LoopError> {
+    let index = grid
+        .data
+        .iter()
+        .position(|&b| b == b'S')
+        .ok_or(LoopError::MissingStart)?;
+    let start = (index % grid.width, index / grid.width);
+    grid[start] = infer_start_pipe(grid, start)?;
+    Ok(start)
+}
+
+/// Follows the pipe at `start` all the way round, returning every point on the loop.
+fn trace_loop(grid: &Grid2D, start: Point2D) -> Result<Vec<Point2D>, LoopError> {
+    let mut point = start;
+    let mut direction = pipe_connections(grid[start]).ok_or(LoopError::DeadEnd(start))?[0];
+    let mut path = vec![start];
+    loop {
+        let next_point = grid
+            .move_from_point(point, direction)
+            .ok_or(LoopError::EdgeExit(point, direction))?;
+        let connections =
+            pipe_connections(grid[next_point]).ok_or(LoopError::DeadEnd(next_point))?;
+
+        // The next pipe has to connect back to where we came from
+        let came_from = direction.opposite();
+        if !connections.contains(&came_from) {
+            return Err(LoopError::OpenPath(next_point));
         }
-        if inside && !on_path {
-            squares_inside += 1;
+        if next_point == start {
+            return Ok(path);
         }
+        direction = if connections[0] == came_from {
+            connections[1]
+        } else {
+            connections[0]
+        };
+        path.push(next_point);
+        point = next_point;
     }
-    squares_inside
+}
+
+fn parse_main_loop(input: &str) -> Result<(Grid2D, Vec<Point2D>), LoopError> {
+    let mut grid = Grid2D::from_lines(input);
+    let start = replace_start(&mut grid)?;
+    let path = trace_loop(&grid, start)?;
+    Ok((grid, path))
+}
+
+/// Finds every pipe which isn't part of the main loop through S.
+pub fn find_junk_pipes(input: &str) -> Result<Vec<Point2D>, LoopError> {
+    let (grid, path) = parse_main_loop(input)?;
+    let on_path: HashSet<Point2D> = path.into_iter().collect();
+    let junk = (0..grid.height)
+        .cartesian_product(0..grid.width)
+        .map(|(y, x)| (x, y))
+        .filter(|&point| pipe_connections(grid[point]).is_some() && !on_path.contains(&point))
+        .collect();
+    Ok(junk)
+}
+
+pub fn day10_star1(input: &str) -> usize {
+    let (_, path) = parse_main_loop(input).unwrap();
+    path.len() / 2
+}
+
+pub fn day10_star2(input: &str) -> usize {
+    let (grid, path) = parse_main_loop(input).unwrap();
+
+    // Straight pipes can't be corners, so leave them out of the polygon
+    let vertices = path
+        .into_iter()
+        .filter(|&point| grid[point] != b'|' && grid[point] != b'-')
+        .map(|(x, y)| (x as i64, y as i64))
+        .collect();
+    Polygon::new(vertices).interior_points() as usize
 }
 
 #[cfg(test)]
@@ -184,4 +234,120 @@ mod tests {
         let actual = day10_star2(&file);
         Ok(assert_eq!(actual, 393))
     }
+
+    #[test]
+    fn day10_infer_start_pipe() {
+        let input = indoc! {"
+            .....
+            .F-7.
+            .|.|.
+            .L-J.
+            ....."};
+        for (start, pipe) in [
+            ((1, 1), b'F'),
+            ((2, 1), b'-'),
+            ((3, 3), b'J'),
+            ((1, 2), b'|'),
+        ] {
+            let mut grid = Grid2D::from_lines(input);
+            grid[start] = b'S';
+            assert_eq!(replace_start(&mut grid).unwrap(), start);
+            assert_eq!(grid[start], pipe);
+            assert_eq!(grid.data, Grid2D::from_lines(input).data);
+        }
+    }
+
+    #[test]
+    fn day10_star2_start_shapes() {
+        // the same loop as the first star 2 example, with S moved onto each corner type
+        let input = indoc! {"
+            ...........
+            .F-------7.
+            .|F-----7|.
+            .||.....||.
+            .||.....||.
+            .|L-7.F-J|.
+            .|..|.|..|.
+            .L--J.L--J.
+            ..........."};
+        for start in [(1, 1), (9, 1), (4, 7), (9, 7), (2, 4), (5, 1)] {
+            let mut grid = Grid2D::from_lines(input);
+            grid[start] = b'S';
+            let string = grid
+                .data
+                .chunks(grid.width)
+                .map(|row| String::from_utf8_lossy(row))
+                .join("\n");
+            assert_eq!(day10_star2(&string), 4);
+        }
+    }
+
+    #[test]
+    fn day10_start_connection_errors() {
+        let actual = replace_start(&mut Grid2D::from_lines(".....\n.....\n..S..\n.....\n....."));
+        assert_eq!(actual, Err(LoopError::StartConnections(vec![])));
+        let actual = replace_start(&mut Grid2D::from_lines(".....\n..|..\n..S..\n.....\n....."));
+        assert_eq!(
+            actual,
+            Err(LoopError::StartConnections(vec![Direction::North]))
+        );
+        let actual = replace_start(&mut Grid2D::from_lines(".....\n..|..\n.-S-.\n.....\n....."));
+        let connected = vec![Direction::North, Direction::West, Direction::East];
+        assert_eq!(actual, Err(LoopError::StartConnections(connected)));
+        let actual = replace_start(&mut Grid2D::from_lines("....."));
+        assert_eq!(actual, Err(LoopError::MissingStart));
+    }
+
+    #[test]
+    fn day10_loop_errors() {
+        let actual = parse_main_loop(indoc! {"
+            S-7
+            |.|
+            L-."});
+        assert_eq!(actual.err(), Some(LoopError::DeadEnd((2, 2))));
+        let actual = parse_main_loop(indoc! {"
+            S-7
+            |.|
+            L-F"});
+        assert_eq!(actual.err(), Some(LoopError::OpenPath((2, 2))));
+        let actual = parse_main_loop(indoc! {"
+            S-7
+            |.|
+            L-|"});
+        assert_eq!(
+            actual.err(),
+            Some(LoopError::EdgeExit((2, 2), Direction::South))
+        );
+    }
+
+    #[test]
+    fn day10_junk_pipes() {
+        let input = indoc! {"
+            -L|F7
+            7S-7|
+            L|7||
+            -L-J|
+            L|-JF"};
+        let actual = find_junk_pipes(input).unwrap();
+        let expected = vec![
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (0, 1),
+            (4, 1),
+            (0, 2),
+            (2, 2),
+            (4, 2),
+            (0, 3),
+            (4, 3),
+            (0, 4),
+            (1, 4),
+            (2, 4),
+            (3, 4),
+            (4, 4),
+        ];
+        assert_eq!(actual, expected);
+    }
 }
@@ -1,68 +1,147 @@
-use crate::common::maths::abs_diff;
+use crate::common::{grid2d::Point2D, maths::abs_diff};
 use itertools::Itertools;
 
-fn sum_paths_between_expanded_galaxies(input: &str, expansion: usize) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let string = &input.lines().join("");
-    let bytes = string.as_bytes();
-    let length = bytes.len();
-
-    let mut galaxies = Vec::new();
-    let mut row_adj = 0;
-    let mut empty_cols = Vec::<usize>::new();
-    let mut row_empty = true;
-    for i in 0..length {
-        let byte = bytes[i];
-        let x = i % width;
-        let y = i / width;
-
-        // Reset at start of row
-        if x == 0 {
-            row_empty = true;
-        }
+/// The positions of every galaxy after the empty rows and columns have expanded.
+/// Galaxies are numbered from 0 in reading order.
+#[derive(Clone, Debug)]
+pub struct GalaxyMap {
+    galaxies: Vec<Point2D>,
+    by_x: Vec<usize>,
+}
 
-        // Handle galaxy!
-        if byte == b'#' {
-            let col_adj = expansion * empty_cols.iter().filter(|&&a| a < x).count();
-            galaxies.push((x + col_adj, y + row_adj));
-            row_empty = false;
-        }
+/// How many rows or columns each empty row or column grows into.
+/// Each multiplier is given the empty line's ordinal among the empty lines on
+/// that axis, counting from 0, and then its index in the original image.
+pub struct Expansion {
+    pub rows: Box<dyn Fn(usize, usize) -> usize>,
+    pub columns: Box<dyn Fn(usize, usize) -> usize>,
+}
+
+impl Expansion {
+    pub fn uniform(multiplier: usize) -> Self {
+        Self::per_axis(multiplier, multiplier)
+    }
 
-        // Adjust if needed at end of row
-        if row_empty && x == width - 1 {
-            row_adj += expansion;
+    pub fn per_axis(row_multiplier: usize, column_multiplier: usize) -> Self {
+        Self {
+            rows: Box::new(move |_, _| row_multiplier),
+            columns: Box::new(move |_, _| column_multiplier),
         }
+    }
+}
+
+fn expanded_positions(
+    empty_lines: &[bool],
+    multiplier: &dyn Fn(usize, usize) -> usize,
+) -> Vec<usize> {
+    let mut position = 0;
+    let mut ordinal = 0;
+    empty_lines
+        .iter()
+        .enumerate()
+        .map(|(index, &empty)| {
+            let current = position;
+            if empty {
+                position += multiplier(ordinal, index);
+                ordinal += 1;
+            } else {
+                position += 1;
+            }
+            current
+        })
+        .collect()
+}
+
+fn sum_axis_distances(mut coords: Vec<usize>) -> usize {
+    // Each coord is further along than all the ones before it once sorted,
+    // so its distance to them is just coord * count - their sum
+    coords.sort_unstable();
+    let mut prefix_sum = 0;
+    let mut distance_sum = 0;
+    for (i, coord) in coords.into_iter().enumerate() {
+        distance_sum += coord * i - prefix_sum;
+        prefix_sum += coord;
+    }
+    distance_sum
+}
+
+impl GalaxyMap {
+    pub fn galaxies(&self) -> &[Point2D] {
+        &self.galaxies
+    }
+
+    pub fn new(input: &str, expansion: &Expansion) -> Self {
+        let rows = input.lines().map(str::as_bytes).collect_vec();
+        let width = rows[0].len();
+        let empty_rows = rows.iter().map(|row| !row.contains(&b'#')).collect_vec();
+        let empty_cols = (0..width)
+            .map(|x| rows.iter().all(|row| row[x] != b'#'))
+            .collect_vec();
+        let xs = expanded_positions(&empty_cols, &expansion.columns);
+        let ys = expanded_positions(&empty_rows, &expansion.rows);
+
+        let galaxies = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().positions(|&b| b == b'#').map(move |x| (x, y)))
+            .map(|(x, y)| (xs[x], ys[y]))
+            .collect_vec();
+        let by_x = (0..galaxies.len())
+            .sorted_unstable_by_key(|&i| (galaxies[i].0, i))
+            .collect();
+        Self { galaxies, by_x }
+    }
+
+    pub fn distance(&self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.galaxies[a], self.galaxies[b]);
+        abs_diff(a.0, b.0) + abs_diff(a.1, b.1)
+    }
+
+    /// The sum of the distances between every pair of galaxies.
+    pub fn sum_of_distances(&self) -> usize {
+        let xs = self.galaxies.iter().map(|galaxy| galaxy.0).collect();
+        let ys = self.galaxies.iter().map(|galaxy| galaxy.1).collect();
+        sum_axis_distances(xs) + sum_axis_distances(ys)
+    }
 
-        // Check for empty columns while we traverse the first row
-        if i < width {
-            let mut x = i;
-            let mut empty = true;
-            while x < length {
-                if bytes[x] != b'.' {
-                    empty = false;
+    /// The closest other galaxy and its distance, preferring lower numbers on ties.
+    pub fn nearest_neighbour(&self, index: usize) -> Option<(usize, usize)> {
+        let x = self.galaxies[index].0;
+        let position = self
+            .by_x
+            .binary_search_by_key(&(x, index), |&i| (self.galaxies[i].0, i))
+            .unwrap();
+
+        // Search outwards along x, stopping once x alone is further than the best so far
+        let mut nearest: Option<(usize, usize)> = None;
+        let sides: [Box<dyn Iterator<Item = &usize>>; 2] = [
+            Box::new(self.by_x[(position + 1)..].iter()),
+            Box::new(self.by_x[..position].iter().rev()),
+        ];
+        for side in sides {
+            for &other in side {
+                if nearest
+                    .is_some_and(|(_, distance)| abs_diff(self.galaxies[other].0, x) > distance)
+                {
                     break;
                 }
-                x += width;
-            }
-            if empty {
-                empty_cols.push(i);
+                let candidate = (other, self.distance(index, other));
+                nearest = nearest
+                    .into_iter()
+                    .chain([candidate])
+                    .min_by_key(|&(other, distance)| (distance, other));
             }
         }
+        nearest
     }
-
-    galaxies
-        .into_iter()
-        .combinations(2)
-        .map(|vec| abs_diff(vec[0].0, vec[1].0) + abs_diff(vec[0].1, vec[1].1))
-        .sum()
 }
 
 pub fn day11_star1(input: &str) -> usize {
-    sum_paths_between_expanded_galaxies(input, 1)
+    GalaxyMap::new(input, &Expansion::uniform(2)).sum_of_distances()
 }
 
 pub fn day11_star2(input: &str, expansion: usize) -> usize {
-    sum_paths_between_expanded_galaxies(input, expansion - 1)
+    GalaxyMap::new(input, &Expansion::uniform(expansion)).sum_of_distances()
 }
 
 #[cfg(test)]
@@ -110,4 +189,115 @@ mod tests {
         let actual = day11_star2(&file, 1_000_000);
         Ok(assert_eq!(actual, 363_293_506_944))
     }
+
+    #[test]
+    fn day11_galaxy_distances() {
+        let map = GalaxyMap::new(EXAMPLE_INPUT, &Expansion::uniform(2));
+        assert_eq!(map.distance(4, 8), 9);
+        assert_eq!(map.distance(0, 6), 15);
+        assert_eq!(map.distance(2, 5), 17);
+        assert_eq!(map.distance(7, 8), 5);
+    }
+
+    #[test]
+    fn day11_nearest_neighbours() {
+        let map = GalaxyMap::new(EXAMPLE_INPUT, &Expansion::uniform(2));
+        for index in 0..map.galaxies.len() {
+            let expected = (0..map.galaxies.len())
+                .filter(|&other| other != index)
+                .map(|other| (other, map.distance(index, other)))
+                .min_by_key(|&(other, distance)| (distance, other));
+            assert_eq!(map.nearest_neighbour(index), expected);
+        }
+    }
+
+    #[test]
+    fn day11_many_galaxies() {
+        // a big pseudo-random map, checked against the quadratic pairwise sum
+        let mut seed: u64 = 11;
+        let input = (0..300)
+            .map(|_| {
+                (0..300)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                        if seed >> 59 == 0 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .join("\n");
+        let map = GalaxyMap::new(&input, &Expansion::uniform(10));
+        let expected: usize = (0..map.galaxies.len())
+            .tuple_combinations()
+            .map(|(a, b)| map.distance(a, b))
+            .sum();
+        assert_eq!(map.sum_of_distances(), expected);
+    }
+
+    #[test]
+    fn day11_per_axis_expansion() {
+        // grow the empty rows by hand, and leave the empty columns alone
+        let grown_rows = EXAMPLE_INPUT
+            .lines()
+            .flat_map(|line| {
+                let copies = if line.contains('#') { 1 } else { 3 };
+                std::iter::repeat_n(line, copies)
+            })
+            .join("\n");
+        let expected = GalaxyMap::new(&grown_rows, &Expansion::uniform(1)).sum_of_distances();
+        let actual = GalaxyMap::new(EXAMPLE_INPUT, &Expansion::per_axis(3, 1)).sum_of_distances();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn day11_expansion_by_index() {
+        // only empty column 2 is before column 4, so it doubles, and the empty rows vanish
+        let expansion = Expansion {
+            rows: Box::new(|_, _| 0),
+            columns: Box::new(|_, x| if x < 4 { 2 } else { 1 }),
+        };
+        let grown = EXAMPLE_INPUT
+            .lines()
+            .filter(|line| line.contains('#'))
+            .map(|line| format!("{}.{}", &line[..2], &line[2..]))
+            .join("\n");
+        let expected = GalaxyMap::new(&grown, &Expansion::uniform(1)).sum_of_distances();
+        let actual = GalaxyMap::new(EXAMPLE_INPUT, &expansion).sum_of_distances();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn day11_expansion_by_ordinal() {
+        // double only the first 2 empty rows, which are rows 2 and 4, leaving row 6 alone
+        let expansion = Expansion {
+            rows: Box::new(|ordinal, _| if ordinal < 2 { 2 } else { 1 }),
+            columns: Box::new(|_, _| 1),
+        };
+        let input = indoc! {"
+            ...#......
+            .......#..
+            ..........
+            #.........
+            ..........
+            .........#
+            ..........
+            ..#......."};
+        let grown = indoc! {"
+            ...#......
+            .......#..
+            ..........
+            ..........
+            #.........
+            ..........
+            ..........
+            .........#
+            ..........
+            ..#......."};
+        let expected = GalaxyMap::new(grown, &Expansion::uniform(1));
+        let actual = GalaxyMap::new(input, &expansion);
+        assert_eq!(actual.galaxies(), expected.galaxies());
+    }
 }
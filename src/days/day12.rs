@@ -1,7 +1,16 @@
+pub mod nonogram;
+
 use itertools::Itertools;
 use lazy_static::lazy_static;
+use num::{BigUint, One, Zero};
 use rayon::prelude::*;
-use std::{collections::HashMap, iter::repeat, sync::RwLock};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead},
+    iter::repeat,
+    ops::{Add, AddAssign, Mul},
+    sync::RwLock,
+};
 
 type Cache = HashMap<(Vec<u8>, Vec<usize>), usize>;
 
@@ -76,18 +85,199 @@ fn count_damage_arrangements_rec(records: &[u8], groups: &[usize]) -> usize {
     count_arrangements_rec(&records[group_length + 1..], &groups[1..])
 }
 
-fn count_arrangements(input: &str) -> usize {
+fn parse_line(input: &str) -> (&str, Vec<usize>) {
     let (record_str, group_str) = input.split_once(' ').unwrap();
-    let groups: Vec<usize> = group_str.split(',').map(|x| x.parse().unwrap()).collect();
+    let groups = group_str.split(',').map(|x| x.parse().unwrap()).collect();
+    (record_str, groups)
+}
+
+fn count_arrangements(input: &str) -> usize {
+    let (record_str, groups) = parse_line(input);
     count_arrangements_rec(record_str.as_bytes(), &groups)
 }
 
-fn unfold_and_count_arrangements(input: &str) -> usize {
-    let (record_str, group_str) = input.split_once(' ').unwrap();
-    let unf_record_str = repeat(record_str).take(5).join("?");
-    let unf_groups = repeat(group_str).take(5).join(",");
-    let groups: Vec<usize> = unf_groups.split(',').map(|x| x.parse().unwrap()).collect();
-    count_arrangements_rec(unf_record_str.as_bytes(), &groups)
+/// Lazily yields every concrete arrangement of a record, with each `?` resolved.
+/// Arrangements come out in lexicographic order, with `.` before `#`.
+pub struct Arrangements {
+    records: Vec<u8>,
+    groups: Vec<usize>,
+    stack: Vec<(usize, usize, Vec<u8>)>,
+}
+
+impl Arrangements {
+    pub fn new(input: &str) -> Self {
+        let (record_str, groups) = parse_line(input);
+        let mut arrangements = Self {
+            records: record_str.as_bytes().to_vec(),
+            groups,
+            stack: Vec::new(),
+        };
+        arrangements.push_if_viable(0, 0, Vec::new());
+        arrangements
+    }
+
+    fn push_if_viable(&mut self, position: usize, group: usize, built: Vec<u8>) {
+        // Use the memoised count to skip any branch with no arrangements in it
+        if count_arrangements_rec(&self.records[position..], &self.groups[group..]) > 0 {
+            self.stack.push((position, group, built));
+        }
+    }
+
+    fn push_group(&mut self, position: usize, group: usize, mut built: Vec<u8>) {
+        let Some(&group_length) = self.groups.get(group) else {
+            return;
+        };
+        let end = position + group_length;
+        if end > self.records.len()
+            || self.records[position..end].contains(&b'.')
+            || self.records.get(end) == Some(&b'#')
+        {
+            return;
+        }
+        built.resize(built.len() + group_length, b'#');
+        if end < self.records.len() {
+            // The spring after a group has to be undamaged
+            built.push(b'.');
+            self.push_if_viable(end + 1, group + 1, built);
+        } else {
+            self.push_if_viable(end, group + 1, built);
+        }
+    }
+}
+
+impl Iterator for Arrangements {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some((position, group, mut built)) = self.stack.pop() {
+            let Some(&spring) = self.records.get(position) else {
+                // Only viable states are stacked, so every group has been placed
+                return Some(String::from_utf8(built).unwrap());
+            };
+            // Stack the damaged branch first so the undamaged one comes out first
+            if spring != b'.' {
+                self.push_group(position, group, built.clone());
+            }
+            if spring != b'#' {
+                built.push(b'.');
+                self.push_if_viable(position + 1, group, built);
+            }
+        }
+        None
+    }
+}
+
+fn count_arrangements_dp<N>(records: &[u8], groups: &[usize]) -> N
+where
+    N: Clone + Zero + One + for<'a> AddAssign<&'a N>,
+{
+    let (n, m) = (records.len(), groups.len());
+
+    // The first undamaged spring at or after each position, to check groups in O(1)
+    let mut next_undamaged = vec![n; n + 1];
+    for i in (0..n).rev() {
+        next_undamaged[i] = if records[i] == b'.' {
+            i
+        } else {
+            next_undamaged[i + 1]
+        };
+    }
+
+    // ways[i * (m + 1) + j] is the number of arrangements of records[i..] with groups[j..]
+    let index = |i: usize, j: usize| i * (m + 1) + j;
+    let mut ways = vec![N::zero(); (n + 1) * (m + 1)];
+    ways[index(n, m)] = N::one();
+    for i in (0..n).rev() {
+        for j in 0..=m {
+            let mut count = N::zero();
+            if records[i] != b'#' {
+                count += &ways[index(i + 1, j)];
+            }
+            if records[i] != b'.'
+                && let Some(&group_length) = groups.get(j)
+                && let end = i + group_length
+                && end <= n
+                && next_undamaged[i] >= end
+                && records.get(end) != Some(&b'#')
+            {
+                count += &ways[index((end + 1).min(n), j + 1)];
+            }
+            ways[index(i, j)] = count;
+        }
+    }
+    ways.swap_remove(0)
+}
+
+/// Whether any arrangement exists, so the DP can be run as a boolean one
+/// where adding is `or` and multiplying is `and`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Possible(bool);
+
+impl Add for Possible {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 || other.0)
+    }
+}
+
+impl AddAssign<&Self> for Possible {
+    fn add_assign(&mut self, other: &Self) {
+        *self = *self + *other;
+    }
+}
+
+impl Mul for Possible {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self(self.0 && other.0)
+    }
+}
+
+impl Zero for Possible {
+    fn zero() -> Self {
+        Self(false)
+    }
+
+    fn is_zero(&self) -> bool {
+        !self.0
+    }
+}
+
+impl One for Possible {
+    fn one() -> Self {
+        Self(true)
+    }
+}
+
+fn has_arrangement(records: &[u8], groups: &[usize]) -> bool {
+    count_arrangements_dp::<Possible>(records, groups).0
+}
+
+/// Counts the arrangements of a record unfolded `factor` times.
+/// Use a big integer for `N` with large factors, as the counts grow exponentially.
+pub fn count_unfolded_arrangements<N>(input: &str, factor: usize) -> N
+where
+    N: Clone + Zero + One + for<'a> AddAssign<&'a N>,
+{
+    let (record_str, groups) = parse_line(input);
+    let unf_record_str = repeat(record_str).take(factor).join("?");
+    let unf_groups = groups.repeat(factor);
+    count_arrangements_dp(unf_record_str.as_bytes(), &unf_groups)
+}
+
+/// Sums the unfolded arrangements of every line from a reader, without
+/// holding the whole input in memory.
+pub fn sum_unfolded_arrangements<R: BufRead + Send>(
+    reader: R,
+    factor: usize,
+) -> io::Result<BigUint> {
+    reader
+        .lines()
+        .par_bridge()
+        .map(|line| line.map(|line| count_unfolded_arrangements::<BigUint>(&line, factor)))
+        .try_reduce(BigUint::zero, |a, b| Ok(a + b))
 }
 
 pub fn day12_star1(input: &str) -> usize {
@@ -95,7 +285,10 @@ pub fn day12_star1(input: &str) -> usize {
 }
 
 pub fn day12_star2(input: &str) -> usize {
-    input.par_lines().map(unfold_and_count_arrangements).sum()
+    input
+        .par_lines()
+        .map(|line| count_unfolded_arrangements::<usize>(line, 5))
+        .sum()
 }
 
 #[cfg(test)]
@@ -104,7 +297,11 @@ mod tests {
     use anyhow::{Ok, Result};
     use indoc::indoc;
     use pretty_assertions::assert_eq;
-    use std::{fs::read_to_string, path::Path};
+    use std::{
+        fs::{read_to_string, File},
+        io::BufReader,
+        path::Path,
+    };
 
     const EXAMPLE_INPUT: &str = indoc! {"
         ???.### 1,1,3
@@ -139,4 +336,64 @@ mod tests {
         let actual = day12_star2(&file);
         Ok(assert_eq!(actual, 7_139_671_893_722))
     }
+
+    #[test]
+    fn day12_enumerate_arrangements() {
+        let actual = Arrangements::new("?###???????? 3,2,1")
+            .take(4)
+            .collect_vec();
+        let expected = vec![
+            ".###....##.#",
+            ".###...##..#",
+            ".###...##.#.",
+            ".###..##...#",
+        ];
+        assert_eq!(actual, expected);
+        let actual = Arrangements::new("???.### 1,1,3").collect_vec();
+        assert_eq!(actual, vec!["#.#.###"]);
+    }
+
+    #[test]
+    fn day12_enumerated_count_matches() {
+        for line in EXAMPLE_INPUT.lines() {
+            let arrangements = Arrangements::new(line).collect_vec();
+            assert!(arrangements.iter().all_unique());
+            assert_eq!(arrangements.len(), count_arrangements(line));
+        }
+    }
+
+    #[test]
+    fn day12_unfold_factors() {
+        for line in EXAMPLE_INPUT.lines() {
+            assert_eq!(
+                count_unfolded_arrangements::<usize>(line, 1),
+                count_arrangements(line)
+            );
+        }
+        let actual = count_unfolded_arrangements::<usize>("????.######..#####. 1,6,5", 3);
+        assert_eq!(actual, 100);
+        // each extra fold multiplies this line's arrangements by 5
+        let actual = count_unfolded_arrangements::<BigUint>("????.######..#####. 1,6,5", 50);
+        assert_eq!(actual, BigUint::from(4_u32) * BigUint::from(5_u32).pow(49));
+    }
+
+    #[test]
+    fn day12_has_arrangement() {
+        for line in EXAMPLE_INPUT.lines() {
+            let (record_str, groups) = parse_line(line);
+            assert!(has_arrangement(record_str.as_bytes(), &groups));
+        }
+        assert!(!has_arrangement(b"#.#", &[3]));
+        assert!(!has_arrangement(b"??", &[1, 1]));
+        assert!(has_arrangement(b"?.?", &[1, 1]));
+    }
+
+    #[test]
+    fn day12_streaming_reader() -> Result<()> {
+        let actual = sum_unfolded_arrangements(EXAMPLE_INPUT.as_bytes(), 5)?;
+        assert_eq!(actual, BigUint::from(525_152_u32));
+        let file = BufReader::new(File::open("inputs/day12.txt")?);
+        let actual = sum_unfolded_arrangements(file, 5)?;
+        Ok(assert_eq!(actual, BigUint::from(7_139_671_893_722_u64)))
+    }
 }
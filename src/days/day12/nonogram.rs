@@ -0,0 +1,320 @@
+use super::has_arrangement;
+use crate::common::grid2d::Grid2D;
+use itertools::Itertools;
+use std::fmt::{self, Write};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NonogramError {
+    InvalidLine(usize),
+    MissingSize,
+    WrongClueCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for NonogramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLine(line) => write!(f, "couldn't parse line {line}"),
+            Self::MissingSize => write!(f, "the puzzle needs a width and a height"),
+            Self::WrongClueCount { expected, found } => {
+                write!(f, "expected {expected} lines of clues, but found {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NonogramError {}
+
+#[derive(Debug)]
+pub enum Solution {
+    None,
+    Unique(Grid2D),
+    Multiple(Grid2D, Grid2D),
+}
+
+/// A nonogram puzzle, where each row and column is a day 12 record that
+/// starts out entirely unknown.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Nonogram {
+    pub rows: Vec<Vec<usize>>,
+    pub columns: Vec<Vec<usize>>,
+}
+
+fn parse_clues(line: &str) -> Option<Vec<usize>> {
+    let clues: Vec<usize> = line
+        .split(|c: char| c == ',' || c.is_ascii_whitespace())
+        .filter(|clue| !clue.is_empty())
+        .map(str::parse)
+        .try_collect()
+        .ok()?;
+    // A lone zero (or nothing at all) means there are no filled cells
+    Some(clues.into_iter().filter(|&clue| clue != 0).collect())
+}
+
+fn format_clues(clues: &[usize]) -> String {
+    if clues.is_empty() {
+        "0".to_owned()
+    } else {
+        clues.iter().join(",")
+    }
+}
+
+fn clues_for_line(line: impl Iterator<Item = u8>) -> Vec<usize> {
+    line.group_by(|&cell| cell == b'#')
+        .into_iter()
+        .filter_map(|(filled, group)| filled.then(|| group.count()))
+        .collect()
+}
+
+/// Fills in every cell of a line which has the same state in all of its arrangements,
+/// or returns `None` if the line has no arrangements at all.
+fn solve_line(line: &[u8], clues: &[usize]) -> Option<Vec<u8>> {
+    let possible = |line: &[u8]| has_arrangement(line, clues);
+    if !possible(line) {
+        return None;
+    }
+    let mut solved = line.to_vec();
+    let mut trial = line.to_vec();
+    for (i, &cell) in line.iter().enumerate() {
+        if cell == b'?' {
+            trial[i] = b'#';
+            let can_fill = possible(&trial);
+            trial[i] = b'.';
+            let can_empty = possible(&trial);
+            trial[i] = b'?';
+            match (can_fill, can_empty) {
+                (true, false) => solved[i] = b'#',
+                (false, true) => solved[i] = b'.',
+                _ => (),
+            }
+        }
+    }
+    Some(solved)
+}
+
+impl Nonogram {
+    /// Parses the usual `.non` format, ignoring any keywords other than the size and clues.
+    pub fn from_non(input: &str) -> Result<Self, NonogramError> {
+        let mut width = None;
+        let mut height = None;
+        let mut rows = Vec::new();
+        let mut columns = Vec::new();
+        let mut lines = input.lines().enumerate().peekable();
+        while let Some((i, line)) = lines.next() {
+            let mut words = line.split_ascii_whitespace();
+            let keyword = words.next();
+            let value = words.next().map(str::parse::<usize>);
+            let clues = match keyword {
+                Some("width") => {
+                    width = Some(
+                        value
+                            .and_then(Result::ok)
+                            .ok_or(NonogramError::InvalidLine(i + 1))?,
+                    );
+                    continue;
+                }
+                Some("height") => {
+                    height = Some(
+                        value
+                            .and_then(Result::ok)
+                            .ok_or(NonogramError::InvalidLine(i + 1))?,
+                    );
+                    continue;
+                }
+                Some("rows") => &mut rows,
+                Some("columns") => &mut columns,
+                _ => continue,
+            };
+            // The clues carry on until the next blank line or keyword
+            while let Some(&(i, line)) = lines.peek()
+                && !line.trim().is_empty()
+                && !line.starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                clues.push(parse_clues(line).ok_or(NonogramError::InvalidLine(i + 1))?);
+                lines.next();
+            }
+        }
+
+        let (width, height) = width.zip(height).ok_or(NonogramError::MissingSize)?;
+        for (expected, found) in [(height, rows.len()), (width, columns.len())] {
+            if expected != found {
+                return Err(NonogramError::WrongClueCount { expected, found });
+            }
+        }
+        Ok(Self { rows, columns })
+    }
+
+    pub fn to_non(&self) -> String {
+        let mut non = String::new();
+        writeln!(non, "width {}", self.columns.len()).unwrap();
+        writeln!(non, "height {}", self.rows.len()).unwrap();
+        for (keyword, lines) in [("rows", &self.rows), ("columns", &self.columns)] {
+            writeln!(non, "\n{keyword}").unwrap();
+            for clues in lines {
+                writeln!(non, "{}", format_clues(clues)).unwrap();
+            }
+        }
+        non
+    }
+
+    /// Works out the clues for a picture drawn with `#` and `.`.
+    pub fn from_picture(input: &str) -> Self {
+        let picture = Grid2D::from_lines(input);
+        let rows = (0..picture.height)
+            .map(|y| clues_for_line((0..picture.width).map(|x| picture[(x, y)])))
+            .collect();
+        let columns = (0..picture.width)
+            .map(|x| clues_for_line((0..picture.height).map(|y| picture[(x, y)])))
+            .collect();
+        Self { rows, columns }
+    }
+
+    /// Repeatedly solves every row and column until nothing changes, returning
+    /// `false` if some line turns out to be impossible.
+    fn propagate(&self, grid: &mut Grid2D) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (y, clues) in self.rows.iter().enumerate() {
+                let line = (0..grid.width).map(|x| grid[(x, y)]).collect_vec();
+                let Some(solved) = solve_line(&line, clues) else {
+                    return false;
+                };
+                for (x, cell) in solved.into_iter().enumerate() {
+                    changed |= grid[(x, y)] != cell;
+                    grid[(x, y)] = cell;
+                }
+            }
+            for (x, clues) in self.columns.iter().enumerate() {
+                let line = (0..grid.height).map(|y| grid[(x, y)]).collect_vec();
+                let Some(solved) = solve_line(&line, clues) else {
+                    return false;
+                };
+                for (y, cell) in solved.into_iter().enumerate() {
+                    changed |= grid[(x, y)] != cell;
+                    grid[(x, y)] = cell;
+                }
+            }
+        }
+        true
+    }
+
+    fn search(&self, mut grid: Grid2D, solutions: &mut Vec<Grid2D>) {
+        if solutions.len() >= 2 || !self.propagate(&mut grid) {
+            return;
+        }
+        // Once propagation stalls, guess the first unknown cell both ways
+        if let Some(index) = grid.data.iter().position(|&cell| cell == b'?') {
+            for guess in *b"#." {
+                let mut guessed = grid.clone();
+                guessed.data[index] = guess;
+                self.search(guessed, solutions);
+            }
+        } else {
+            solutions.push(grid);
+        }
+    }
+
+    /// Solves the puzzle, finding up to two solutions to check it's unique.
+    pub fn solve(&self) -> Solution {
+        let (width, height) = (self.columns.len(), self.rows.len());
+        let grid = Grid2D {
+            data: vec![b'?'; width * height],
+            width,
+            height,
+        };
+        let mut solutions = Vec::new();
+        self.search(grid, &mut solutions);
+        let mut solutions = solutions.into_iter();
+        match (solutions.next(), solutions.next()) {
+            (Some(first), Some(second)) => Solution::Multiple(first, second),
+            (Some(solution), None) => Solution::Unique(solution),
+            _ => Solution::None,
+        }
+    }
+}
+
+/// Draws a grid with `#` and `.`, in the same format `from_picture` reads.
+pub fn render_picture(grid: &Grid2D) -> String {
+    grid.data
+        .chunks(grid.width)
+        .map(|row| String::from_utf8_lossy(row))
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    const PICTURE: &str = indoc! {"
+        .###.
+        ##.##
+        #####
+        #...#
+        .###."};
+
+    const NON: &str = indoc! {r#"
+        title "ring"
+        width 5
+        height 5
+
+        rows
+        3
+        2,2
+        5
+        1,1
+        3
+
+        columns
+        3
+        3,1
+        1,1,1
+        3,1
+        3
+    "#};
+
+    #[test]
+    fn read_and_write_non() {
+        let nonogram = Nonogram::from_non(NON).unwrap();
+        assert_eq!(nonogram, Nonogram::from_picture(PICTURE));
+        let written = nonogram.to_non();
+        assert_eq!(Nonogram::from_non(&written).unwrap(), nonogram);
+    }
+
+    #[test]
+    fn solve_unique() {
+        let Solution::Unique(solution) = Nonogram::from_picture(PICTURE).solve() else {
+            panic!("expected a unique solution");
+        };
+        assert_eq!(render_picture(&solution), PICTURE);
+    }
+
+    #[test]
+    fn solve_needs_backtracking() {
+        // propagation alone can't place anything here, as it's a diagonal either way
+        let nonogram = Nonogram::from_picture("#.\n.#");
+        let Solution::Multiple(first, second) = nonogram.solve() else {
+            panic!("expected multiple solutions");
+        };
+        assert_eq!(render_picture(&first), "#.\n.#");
+        assert_eq!(render_picture(&second), ".#\n#.");
+    }
+
+    #[test]
+    fn solve_impossible() {
+        let nonogram = Nonogram {
+            rows: vec![vec![2], vec![]],
+            columns: vec![vec![], vec![1]],
+        };
+        assert!(matches!(nonogram.solve(), Solution::None));
+        let actual = Nonogram::from_non("width 2\nheight 2\nrows\n1\n\ncolumns\n1\n1");
+        assert_eq!(
+            actual,
+            Err(NonogramError::WrongClueCount {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
@@ -1,92 +1,195 @@
+use crate::common::grid2d::Point2D;
 use itertools::Itertools;
+use std::fmt;
 
-fn calculate_reflections(grid: &[&[u8]], allowed_differences: usize) -> usize {
-    let width = grid[0].len();
-    let height = grid.len();
-
-    // Check horizontal line reflection!
-    for y in 0..(height - 1) {
-        let mut rows_differences = 0;
-        for x in 0..width {
-            if grid[y][x] != grid[y + 1][x] {
-                rows_differences += 1;
-                if rows_differences > allowed_differences {
-                    break;
-                }
-            }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reflection {
+    pub orientation: Orientation,
+    /// The number of rows above, or columns to the left of, the line.
+    pub position: usize,
+    /// Each pair of mirrored cells that don't match. Flipping either cell of a
+    /// pair would make that part of the reflection perfect.
+    pub smudges: Vec<[Point2D; 2]>,
+}
+
+impl Reflection {
+    pub const fn summary(&self) -> usize {
+        match self.orientation {
+            Orientation::Horizontal => 100 * self.position,
+            Orientation::Vertical => self.position,
         }
-        if rows_differences <= allowed_differences {
-            for offset in 1..=y {
-                let lower = y - offset;
-                let upper = y + 1 + offset;
-                if upper >= height {
-                    break;
-                }
-                for x in 0..width {
-                    if grid[lower][x] != grid[upper][x] {
-                        rows_differences += 1;
-                        if rows_differences > allowed_differences {
-                            break;
-                        }
-                    }
-                }
-            }
-            if rows_differences == allowed_differences {
-                return 100 * (y + 1);
-            }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReflectionError {
+    NoReflection,
+    MultipleReflections(Vec<Reflection>),
+}
+
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoReflection => write!(f, "the pattern has no line of reflection"),
+            Self::MultipleReflections(reflections) => write!(
+                f,
+                "the pattern has {} lines of reflection",
+                reflections.len()
+            ),
         }
     }
+}
 
-    // Check vertical line reflection!
-    for x in 0..(width - 1) {
-        let mut columns_differences = 0;
-        for line in grid {
-            if line[x] != line[x + 1] {
-                columns_differences += 1;
-                if columns_differences > allowed_differences {
-                    break;
-                }
-            }
+impl std::error::Error for ReflectionError {}
+
+/// Each row and column of a pattern packed into bits, with `#` as a set bit.
+/// Lines are split across as many words as needed, so any width works.
+struct Pattern {
+    rows: Vec<Vec<u64>>,
+    columns: Vec<Vec<u64>>,
+}
+
+fn pack_line(cells: impl Iterator<Item = u8>) -> Vec<u64> {
+    let mut words = Vec::new();
+    for (i, cell) in cells.enumerate() {
+        if i % 64 == 0 {
+            words.push(0);
+        }
+        if cell == b'#' {
+            words[i / 64] |= 1 << (i % 64);
         }
-        if columns_differences <= allowed_differences {
-            for offset in 1..=x {
-                let left = x - offset;
-                let right = x + 1 + offset;
-                if right >= width {
-                    break;
-                }
-                for line in grid {
-                    if line[left] != line[right] {
-                        columns_differences += 1;
-                        if columns_differences > allowed_differences {
-                            break;
-                        }
-                    }
-                }
+    }
+    words
+}
+
+fn count_differences(a: &[u64], b: &[u64]) -> usize {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a ^ b).count_ones() as usize)
+        .sum()
+}
+
+fn differing_cells<'a>(a: &'a [u64], b: &'a [u64]) -> impl Iterator<Item = usize> + 'a {
+    a.iter()
+        .zip(b)
+        .enumerate()
+        .flat_map(|(word_index, (a, b))| {
+            let xor = a ^ b;
+            (0..64)
+                .filter(move |bit| xor & (1 << bit) != 0)
+                .map(move |bit| word_index * 64 + bit)
+        })
+}
+
+impl Pattern {
+    fn new(grid: &[&[u8]]) -> Self {
+        let rows = grid
+            .iter()
+            .map(|row| pack_line(row.iter().copied()))
+            .collect();
+        let columns = (0..grid[0].len())
+            .map(|x| pack_line(grid.iter().map(|row| row[x])))
+            .collect();
+        Self { rows, columns }
+    }
+
+    fn lines(&self, orientation: Orientation) -> &[Vec<u64>] {
+        match orientation {
+            Orientation::Horizontal => &self.rows,
+            Orientation::Vertical => &self.columns,
+        }
+    }
+
+    fn find_smudges(
+        &self,
+        orientation: Orientation,
+        position: usize,
+        allowed_differences: usize,
+    ) -> Option<Vec<[Point2D; 2]>> {
+        let lines = self.lines(orientation);
+        let point = |line, cell| match orientation {
+            Orientation::Horizontal => (cell, line),
+            Orientation::Vertical => (line, cell),
+        };
+
+        // Work outwards from the line until one side runs out
+        let mirrored_pairs = (0..position.min(lines.len() - position))
+            .map(|offset| (position - 1 - offset, position + offset));
+        let mut differences = 0;
+        for (a, b) in mirrored_pairs.clone() {
+            differences += count_differences(&lines[a], &lines[b]);
+            if differences > allowed_differences {
+                return None;
             }
-            if columns_differences == allowed_differences {
-                return x + 1;
+        }
+        let smudges = mirrored_pairs
+            .flat_map(|(a, b)| {
+                differing_cells(&lines[a], &lines[b])
+                    .map(move |cell| [point(a, cell), point(b, cell)])
+            })
+            .collect();
+        Some(smudges)
+    }
+}
+
+/// Finds every line of reflection with exactly `allowed_differences` smudges.
+pub fn find_reflections(grid: &[&[u8]], allowed_differences: usize) -> Vec<Reflection> {
+    let pattern = Pattern::new(grid);
+    let mut reflections = Vec::new();
+    for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+        for position in 1..pattern.lines(orientation).len() {
+            if let Some(smudges) = pattern.find_smudges(orientation, position, allowed_differences)
+                && smudges.len() == allowed_differences
+            {
+                reflections.push(Reflection {
+                    orientation,
+                    position,
+                    smudges,
+                });
             }
         }
     }
+    reflections
+}
 
-    0
+/// The one line of reflection, or an error if there isn't exactly one.
+pub fn find_reflection(
+    grid: &[&[u8]],
+    allowed_differences: usize,
+) -> Result<Reflection, ReflectionError> {
+    let mut reflections = find_reflections(grid, allowed_differences);
+    match reflections.len() {
+        0 => Err(ReflectionError::NoReflection),
+        1 => Ok(reflections.remove(0)),
+        _ => Err(ReflectionError::MultipleReflections(reflections)),
+    }
 }
 
-pub fn day13_star1(input: &str) -> usize {
+fn summarise_patterns(input: &str, allowed_differences: usize) -> usize {
     let lines = &input.lines().map(str::as_bytes).collect_vec();
     lines
         .split(|bytes| bytes.is_empty())
-        .map(|grid| calculate_reflections(grid, 0))
+        .map(|grid| {
+            // Like the puzzle, take the first line we find, and count nothing if there's none
+            find_reflections(grid, allowed_differences)
+                .first()
+                .map_or(0, Reflection::summary)
+        })
         .sum()
 }
 
+pub fn day13_star1(input: &str) -> usize {
+    summarise_patterns(input, 0)
+}
+
 pub fn day13_star2(input: &str) -> usize {
-    let lines = &input.lines().map(str::as_bytes).collect_vec();
-    lines
-        .split(|bytes| bytes.is_empty())
-        .map(|grid| calculate_reflections(grid, 1))
-        .sum()
+    summarise_patterns(input, 1)
 }
 
 #[cfg(test)]
@@ -139,4 +242,65 @@ mod tests {
         let actual = day13_star2(&file);
         Ok(assert_eq!(actual, 30442))
     }
+
+    #[test]
+    fn day13_reflection_smudges() {
+        let lines = EXAMPLE_INPUT.lines().map(str::as_bytes).collect_vec();
+        let patterns = lines.split(|bytes| bytes.is_empty()).collect_vec();
+        let expected = Reflection {
+            orientation: Orientation::Vertical,
+            position: 5,
+            smudges: vec![],
+        };
+        assert_eq!(find_reflection(patterns[0], 0).unwrap(), expected);
+        let expected = Reflection {
+            orientation: Orientation::Horizontal,
+            position: 3,
+            smudges: vec![[(0, 0), (0, 5)]],
+        };
+        assert_eq!(find_reflection(patterns[0], 1).unwrap(), expected);
+        let expected = Reflection {
+            orientation: Orientation::Horizontal,
+            position: 1,
+            smudges: vec![[(4, 0), (4, 1)]],
+        };
+        assert_eq!(find_reflection(patterns[1], 1).unwrap(), expected);
+    }
+
+    #[test]
+    fn day13_multiple_and_no_reflections() {
+        let grid: &[&[u8]] = &[b"#.", b".."];
+        assert_eq!(find_reflection(grid, 0), Err(ReflectionError::NoReflection));
+        let actual = find_reflections(grid, 1)
+            .into_iter()
+            .map(|reflection| (reflection.orientation, reflection.smudges))
+            .collect_vec();
+        let expected = vec![
+            (Orientation::Horizontal, vec![[(0, 0), (0, 1)]]),
+            (Orientation::Vertical, vec![[(0, 0), (1, 0)]]),
+        ];
+        assert_eq!(actual, expected);
+        // the star functions still give a summary for these, rather than failing
+        assert_eq!(day13_star1("#.\n.."), 0);
+        assert_eq!(day13_star2("#.\n.."), 100);
+    }
+
+    #[test]
+    fn day13_wide_patterns() {
+        // a 100 wide row spans two words, and this smudge is past the first word
+        let left = "#..##.#...".repeat(5);
+        let right: String = left.chars().rev().collect();
+        let mut smudged = format!("{left}{right}").into_bytes();
+        smudged[70] = b'#';
+        let grid: &[&[u8]] = &[&smudged];
+        let expected = Reflection {
+            orientation: Orientation::Vertical,
+            position: 50,
+            smudges: vec![[(29, 0), (70, 0)]],
+        };
+        let actual = find_reflections(grid, 1)
+            .into_iter()
+            .find(|reflection| reflection.position == 50);
+        assert_eq!(actual, Some(expected));
+    }
 }
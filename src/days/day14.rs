@@ -1,96 +1,211 @@
 use crate::common::direction::Direction;
+use bit_vec::BitVec;
 use itertools::Itertools;
+use std::{collections::HashMap, fmt, str::FromStr};
 
-fn iter_for_dir(length: usize, dir: Direction) -> Box<dyn Iterator<Item = usize>> {
-    match dir {
-        Direction::North | Direction::West => Box::new(0..length),
-        Direction::South | Direction::East => Box::new((0..length).rev()),
+/// A platform of rounded (`O`) and cube-shaped (`#`) rocks.
+/// For each tilt direction, the cells are split into segments between cube rocks,
+/// ordered from the edge the rocks roll towards, so a tilt just counts the
+/// rounded rocks in each segment and packs them at its start.
+pub struct Platform {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<u8>,
+    segments: [Vec<Vec<usize>>; 4],
+}
+
+impl Platform {
+    pub fn new(input: &str) -> Self {
+        let width = input.lines().next().unwrap().len();
+        let height = input.lines().count();
+        let cells = input.lines().join("").into_bytes();
+        let mut segments: [Vec<Vec<usize>>; 4] = Default::default();
+        for dir in Direction::NWSE {
+            segments[Self::segment_index(dir)] = Self::find_segments(&cells, width, height, dir);
+        }
+        Self {
+            width,
+            height,
+            cells,
+            segments,
+        }
+    }
+
+    const fn segment_index(dir: Direction) -> usize {
+        match dir {
+            Direction::North => 0,
+            Direction::West => 1,
+            Direction::South => 2,
+            Direction::East => 3,
+        }
+    }
+
+    fn find_segments(cells: &[u8], width: usize, height: usize, dir: Direction) -> Vec<Vec<usize>> {
+        let lines: Vec<Vec<usize>> = match dir {
+            Direction::North => (0..width)
+                .map(|x| (0..height).map(|y| y * width + x).collect())
+                .collect(),
+            Direction::South => (0..width)
+                .map(|x| (0..height).rev().map(|y| y * width + x).collect())
+                .collect(),
+            Direction::West => (0..height)
+                .map(|y| (0..width).map(|x| y * width + x).collect())
+                .collect(),
+            Direction::East => (0..height)
+                .map(|y| (0..width).rev().map(|x| y * width + x).collect())
+                .collect(),
+        };
+        lines
+            .iter()
+            .flat_map(|line| line.split(|&i| cells[i] == b'#'))
+            .filter(|segment| !segment.is_empty())
+            .map(<[usize]>::to_vec)
+            .collect()
+    }
+
+    pub fn tilt(&mut self, dir: Direction) {
+        for segment in &self.segments[Self::segment_index(dir)] {
+            let rounded = segment.iter().filter(|&&i| self.cells[i] == b'O').count();
+            for (n, &i) in segment.iter().enumerate() {
+                self.cells[i] = if n < rounded { b'O' } else { b'.' };
+            }
+        }
+    }
+
+    /// One bit per cell for the rounded rocks, as the cube rocks never move.
+    pub fn rounded_rocks(&self) -> BitVec {
+        BitVec::from_fn(self.cells.len(), |i| self.cells[i] == b'O')
+    }
+
+    /// The total load on the support beams along one edge of the platform,
+    /// where each rock's load is its distance from the opposite edge.
+    pub fn support_load(&self, edge: Direction) -> usize {
+        let count_load = |(i, &byte)| {
+            if byte != b'O' {
+                return 0;
+            }
+            let (x, y) = (i % self.width, i / self.width);
+            match edge {
+                Direction::North => self.height - y,
+                Direction::South => y + 1,
+                Direction::West => self.width - x,
+                Direction::East => x + 1,
+            }
+        };
+        self.cells.iter().enumerate().map(count_load).sum()
     }
 }
 
-fn shift_rocks(state: &mut [u8], width: usize, dir: Direction) {
-    for index in iter_for_dir(state.len(), dir) {
-        if state[index] == b'O' {
-            let mut space = index;
-            match dir {
-                Direction::North => {
-                    while let Some(next_space) = space.checked_sub(width)
-                        && state[next_space] == b'.'
-                    {
-                        space = next_space;
-                    }
-                }
-                Direction::West => {
-                    while let Some(next_space) = space.checked_sub(1)
-                        && space % width != 0
-                        && state[next_space] == b'.'
-                    {
-                        space = next_space;
+#[derive(Debug, PartialEq, Eq)]
+pub enum TiltProgramError {
+    UnknownToken(String),
+    InvalidCount(String),
+    NothingToRepeat,
+}
+
+impl fmt::Display for TiltProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownToken(token) => write!(f, "unknown token {token:?}"),
+            Self::InvalidCount(token) => write!(f, "invalid repeat count {token:?}"),
+            Self::NothingToRepeat => write!(f, "a repeat count has no tilts before it"),
+        }
+    }
+}
+
+impl std::error::Error for TiltProgramError {}
+
+/// A sequence of tilts, written like `N W S E x1e9` or `N x3 E`.
+/// Each `x` count repeats all the tilts since the previous count.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TiltProgram {
+    pub segments: Vec<(Vec<Direction>, usize)>,
+}
+
+fn parse_count(token: &str) -> Option<usize> {
+    match token.split_once('e') {
+        Some((mantissa, exponent)) => mantissa
+            .parse::<usize>()
+            .ok()?
+            .checked_mul(10_usize.checked_pow(exponent.parse().ok()?)?),
+        None => token.parse().ok(),
+    }
+}
+
+impl FromStr for TiltProgram {
+    type Err = TiltProgramError;
+
+    fn from_str(input: &str) -> Result<Self, TiltProgramError> {
+        let mut segments = Vec::new();
+        let mut tilts = Vec::new();
+        for token in input.split_ascii_whitespace() {
+            let tilt = match token {
+                "N" => Direction::North,
+                "W" => Direction::West,
+                "S" => Direction::South,
+                "E" => Direction::East,
+                _ if let Some(count_str) = token.strip_prefix('x') => {
+                    let count = parse_count(count_str)
+                        .ok_or_else(|| TiltProgramError::InvalidCount(token.to_owned()))?;
+                    if tilts.is_empty() {
+                        return Err(TiltProgramError::NothingToRepeat);
                     }
+                    segments.push((std::mem::take(&mut tilts), count));
+                    continue;
                 }
-                Direction::South => {
-                    while let next_space = space + width
-                        && next_space < state.len()
-                        && state[next_space] == b'.'
-                    {
-                        space = next_space;
+                _ => return Err(TiltProgramError::UnknownToken(token.to_owned())),
+            };
+            tilts.push(tilt);
+        }
+        if !tilts.is_empty() {
+            segments.push((tilts, 1));
+        }
+        Ok(Self { segments })
+    }
+}
+
+impl TiltProgram {
+    pub fn run(&self, platform: &mut Platform) {
+        for (tilts, count) in &self.segments {
+            // Keep track of every state we've seen, so we can skip ahead once they repeat
+            let mut prev_states = HashMap::new();
+            let mut i = 0;
+            while i < *count {
+                if let Some(prev) = prev_states.insert(platform.rounded_rocks(), i) {
+                    let cycle_length = i - prev;
+                    i = count - (count - i) % cycle_length;
+                    prev_states.clear();
+                    if i == *count {
+                        break;
                     }
                 }
-                Direction::East => {
-                    while let next_space = space + 1
-                        && space % width != width - 1
-                        && state[next_space] == b'.'
-                    {
-                        space = next_space;
-                    }
+                for &dir in tilts {
+                    platform.tilt(dir);
                 }
+                i += 1;
             }
-            state[index] = b'.';
-            state[space] = b'O';
         }
     }
 }
 
-fn count_northern_support_load(state: &[u8], width: usize, height: usize) -> usize {
-    let count_load = |(i, &byte)| {
-        if byte == b'O' {
-            height - i / width
-        } else {
-            0
-        }
-    };
-    state.iter().enumerate().map(count_load).sum()
+/// Runs a tilt program on the platform, then measures the load against one edge.
+pub fn run_tilt_program(
+    input: &str,
+    program: &str,
+    edge: Direction,
+) -> Result<usize, TiltProgramError> {
+    let program: TiltProgram = program.parse()?;
+    let mut platform = Platform::new(input);
+    program.run(&mut platform);
+    Ok(platform.support_load(edge))
 }
 
 pub fn day14_star1(input: &str) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-    let mut state = input.lines().join("").bytes().collect_vec();
-    shift_rocks(&mut state, width, Direction::North);
-    count_northern_support_load(&state, width, height)
+    run_tilt_program(input, "N", Direction::North).unwrap()
 }
 
 pub fn day14_star2(input: &str) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-    let mut state = input.lines().join("").bytes().collect_vec();
-    let mut prev_states = vec![state.clone()];
-    let mut cycle_start = 0;
-    let mut cycle_length = 0;
-    for i in 1..1000 {
-        for dir in Direction::NWSE {
-            shift_rocks(&mut state, width, dir);
-        }
-        if let Some(pos) = prev_states.iter().position(|prev| *prev == state) {
-            cycle_start = pos;
-            cycle_length = i - pos;
-            break;
-        }
-        prev_states.push(state.clone());
-    }
-    let cycles_to_go = 1_000_000_000 - cycle_start;
-    let final_index = cycle_start + (cycles_to_go % cycle_length);
-    count_northern_support_load(&prev_states[final_index], width, height)
+    run_tilt_program(input, "N W S E x1e9", Direction::North).unwrap()
 }
 
 #[cfg(test)]
@@ -138,4 +253,58 @@ mod tests {
         let actual = day14_star2(&file);
         Ok(assert_eq!(actual, 96_105))
     }
+
+    #[test]
+    fn day14_parse_tilt_program() {
+        let actual: TiltProgram = "N x3 E".parse().unwrap();
+        let expected = vec![(vec![Direction::North], 3), (vec![Direction::East], 1)];
+        assert_eq!(actual.segments, expected);
+        let actual: TiltProgram = "N W S E x1e9".parse().unwrap();
+        assert_eq!(actual.segments[0].1, 1_000_000_000);
+        assert_eq!(
+            "x2 N".parse::<TiltProgram>(),
+            Err(TiltProgramError::NothingToRepeat)
+        );
+        let actual = "N Q".parse::<TiltProgram>();
+        assert_eq!(actual, Err(TiltProgramError::UnknownToken("Q".to_owned())));
+        let actual = "N x1e99".parse::<TiltProgram>();
+        assert_eq!(
+            actual,
+            Err(TiltProgramError::InvalidCount("x1e99".to_owned()))
+        );
+    }
+
+    #[test]
+    fn day14_loads_against_each_edge() {
+        let load = |program, edge| run_tilt_program(EXAMPLE_INPUT, program, edge).unwrap();
+        assert_eq!(load("N W S E", Direction::North), 87);
+        assert_eq!(load("N W S E x3", Direction::North), 69);
+        assert_eq!(load("W", Direction::West), 147);
+        assert_eq!(load("S", Direction::South), 132);
+        assert_eq!(load("N", Direction::East), 77);
+        assert_eq!(load("E x1e9", Direction::East), 105);
+        assert_eq!(load("N E x3", Direction::South), 53);
+    }
+
+    #[test]
+    fn day14_tilt_packs_segments() {
+        let mut platform = Platform::new(EXAMPLE_INPUT);
+        platform.tilt(Direction::North);
+        let expected = indoc! {"
+            OOOO.#.O..
+            OO..#....#
+            OO..O##..O
+            O..#.OO...
+            ........#.
+            ..#....#.#
+            ..O..#.O.O
+            ..O.......
+            #....###..
+            #....#...."};
+        assert_eq!(platform.cells, expected.lines().join("").into_bytes());
+        assert_eq!(
+            platform.rounded_rocks().iter().filter(|&bit| bit).count(),
+            18
+        );
+    }
 }
@@ -1,74 +1,286 @@
-#[derive(Clone, Debug)]
-struct Lens {
-    pub label: String,
-    pub focal_length: usize,
+use crate::common::holiday_hashmap::{hashify, HolidayHashMap};
+use itertools::Itertools;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation<'a> {
+    Insert { label: &'a str, focal_length: u8 },
+    Remove { label: &'a str },
 }
 
-fn hashify(step: &str) -> usize {
-    step.bytes()
-        .fold(0, |acc, byte| ((acc + byte as usize) * 17) % 256)
+/// A malformed step, where `position` is the 1-based index of the step.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationError {
+    MissingOperation {
+        position: usize,
+        step: String,
+    },
+    InvalidLabel {
+        position: usize,
+        label: String,
+    },
+    InvalidFocalLength {
+        position: usize,
+        focal_length: String,
+    },
 }
 
-fn get_lens_box<'a>(label: &str, slots: &'a mut [Option<Vec<Lens>>]) -> &'a mut Vec<Lens> {
-    let slot = &mut slots[hashify(label)];
-    if slot.is_none() {
-        slot.replace(Vec::new());
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOperation { position, step } => {
+                write!(
+                    f,
+                    "step {position} ({step:?}) doesn't end in `-` or `=` and a focal length"
+                )
+            }
+            Self::InvalidLabel { position, label } => {
+                write!(f, "step {position} has an invalid label {label:?}")
+            }
+            Self::InvalidFocalLength {
+                position,
+                focal_length,
+            } => write!(
+                f,
+                "step {position} has focal length {focal_length:?}, which isn't 1 to 9"
+            ),
+        }
     }
-    slot.as_mut().unwrap()
 }
 
-fn update_lenses(step: &str, slots: &mut [Option<Vec<Lens>>]) {
-    if let Some((label, focal_str)) = step.split_once('=') {
-        // Replace or add lens!
-        let lens_box = get_lens_box(label, slots);
-        let matching_lens_index = lens_box.iter().position(|lens| lens.label == label);
-        let focal_length = focal_str.parse().unwrap();
-        if let Some(index) = matching_lens_index {
-            lens_box[index].focal_length = focal_length;
-        } else {
-            lens_box.push(Lens {
-                label: label.to_owned(),
-                focal_length,
+impl std::error::Error for OperationError {}
+
+/// The comma-separated steps, with any surrounding whitespace and newlines removed.
+fn steps(input: &str) -> impl Iterator<Item = &str> {
+    input.trim().split(',').map(str::trim)
+}
+
+fn parse_operation(position: usize, step: &str) -> Result<Operation<'_>, OperationError> {
+    let (label, operation) = if let Some((label, focal_str)) = step.split_once('=') {
+        let Ok(focal_length @ 1..=9) = focal_str.parse() else {
+            return Err(OperationError::InvalidFocalLength {
+                position,
+                focal_length: focal_str.to_owned(),
             });
-        }
+        };
+        (
+            label,
+            Operation::Insert {
+                label,
+                focal_length,
+            },
+        )
+    } else if let Some(label) = step.strip_suffix('-') {
+        (label, Operation::Remove { label })
     } else {
-        // Remove lens!
-        let label = &step[0..(step.len() - 1)];
-        let lens_box = get_lens_box(label, slots);
-        if let Some(index) = lens_box.iter().position(|lens| lens.label == label) {
-            lens_box.remove(index);
-        }
+        return Err(OperationError::MissingOperation {
+            position,
+            step: step.to_owned(),
+        });
+    };
+    if label.is_empty() || !label.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+        return Err(OperationError::InvalidLabel {
+            position,
+            label: label.to_owned(),
+        });
     }
+    Ok(operation)
 }
 
-fn calculate_focusing_power(box_index: usize, lens_box: Vec<Lens>) -> usize {
-    lens_box
-        .into_iter()
+pub fn parse_operations(input: &str) -> Result<Vec<Operation<'_>>, OperationError> {
+    steps(input)
         .enumerate()
-        .map(|(lens_index, lens)| (box_index + 1) * (lens_index + 1) * lens.focal_length)
-        .sum()
+        .map(|(i, step)| parse_operation(i + 1, step))
+        .collect()
+}
+
+fn update_lenses(operation: Operation, lenses: &mut HolidayHashMap<u8>) {
+    match operation {
+        Operation::Insert {
+            label,
+            focal_length,
+        } => lenses.insert(label, focal_length),
+        Operation::Remove { label } => lenses.remove(label),
+    };
+}
+
+/// The lenses in every box after one step, as `(box index, label, focal length)`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceStep {
+    pub step: String,
+    pub lenses: Vec<(usize, String, usize)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LensChange {
+    Added {
+        box_index: usize,
+        label: String,
+        focal_length: usize,
+    },
+    Removed {
+        box_index: usize,
+        label: String,
+        focal_length: usize,
+    },
+    Refocused {
+        box_index: usize,
+        label: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl fmt::Display for LensChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added {
+                box_index,
+                label,
+                focal_length,
+            } => {
+                write!(f, "Box {box_index}: + [{label} {focal_length}]")
+            }
+            Self::Removed {
+                box_index,
+                label,
+                focal_length,
+            } => {
+                write!(f, "Box {box_index}: - [{label} {focal_length}]")
+            }
+            Self::Refocused {
+                box_index,
+                label,
+                from,
+                to,
+            } => {
+                write!(f, "Box {box_index}: [{label} {from}] -> [{label} {to}]")
+            }
+        }
+    }
+}
+
+impl TraceStep {
+    fn lenses_by_box(&self) -> Vec<(usize, Vec<(&str, usize)>)> {
+        let groups = self.lenses.iter().group_by(|(box_index, _, _)| *box_index);
+        groups
+            .into_iter()
+            .map(|(box_index, lenses)| {
+                let lenses = lenses.map(|(_, label, focal)| (label.as_str(), *focal));
+                (box_index, lenses.collect_vec())
+            })
+            .collect()
+    }
+
+    /// A single line of JSON, listing only the boxes with lenses in them.
+    pub fn to_json(&self) -> String {
+        let boxes = self.lenses_by_box().into_iter().map(|(box_index, lenses)| {
+            let lenses = lenses.iter().map(|(label, focal_length)| {
+                format!("{{\"label\":{label:?},\"focal_length\":{focal_length}}}")
+            });
+            format!(
+                "{{\"box\":{box_index},\"lenses\":[{}]}}",
+                lenses.format(",")
+            )
+        });
+        format!(
+            "{{\"step\":{:?},\"boxes\":[{}]}}",
+            self.step,
+            boxes.format(",")
+        )
+    }
+
+    /// The lenses that were added, removed or refocused since a previous step.
+    pub fn diff(&self, previous: &Self) -> Vec<LensChange> {
+        let find = |lenses: &[(usize, String, usize)], label: &str| {
+            lenses
+                .iter()
+                .find(|(_, other, _)| other == label)
+                .map(|&(_, _, focal)| focal)
+        };
+        let removed = previous
+            .lenses
+            .iter()
+            .filter(|(_, label, _)| find(&self.lenses, label).is_none())
+            .map(|(box_index, label, focal_length)| LensChange::Removed {
+                box_index: *box_index,
+                label: label.clone(),
+                focal_length: *focal_length,
+            });
+        let added_or_refocused =
+            self.lenses
+                .iter()
+                .filter_map(|(box_index, label, focal_length)| {
+                    match find(&previous.lenses, label) {
+                        None => Some(LensChange::Added {
+                            box_index: *box_index,
+                            label: label.clone(),
+                            focal_length: *focal_length,
+                        }),
+                        Some(from) if from != *focal_length => Some(LensChange::Refocused {
+                            box_index: *box_index,
+                            label: label.clone(),
+                            from,
+                            to: *focal_length,
+                        }),
+                        Some(_) => None,
+                    }
+                });
+        removed.chain(added_or_refocused).collect()
+    }
+}
+
+/// Formatted just like the puzzle statement's walkthrough.
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "After {:?}:", self.step)?;
+        for (box_index, lenses) in self.lenses_by_box() {
+            let lenses = lenses
+                .iter()
+                .map(|(label, focal)| format!("[{label} {focal}]"));
+            writeln!(f, "Box {box_index}: {}", lenses.format(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs every step, recording the contents of the boxes after each one.
+pub fn trace_lenses(input: &str) -> Result<Vec<TraceStep>, OperationError> {
+    let mut lenses = HolidayHashMap::new();
+    let operations = parse_operations(input)?;
+    let trace = steps(input)
+        .zip(operations)
+        .map(|(step, operation)| {
+            update_lenses(operation, &mut lenses);
+            TraceStep {
+                step: step.to_owned(),
+                lenses: lenses
+                    .iter()
+                    .map(|(box_index, label, &focal)| (box_index, label.to_owned(), focal.into()))
+                    .collect(),
+            }
+        })
+        .collect();
+    Ok(trace)
 }
 
 pub fn day15_star1(input: &str) -> usize {
-    input.split(',').map(hashify).sum()
+    steps(input).map(hashify).sum()
 }
 
 pub fn day15_star2(input: &str) -> usize {
-    let mut slots: [Option<Vec<Lens>>; 256] = std::array::from_fn(|_| None);
-    for step in input.split(',') {
-        update_lenses(step, &mut slots);
+    let mut lenses = HolidayHashMap::new();
+    for operation in parse_operations(input).unwrap() {
+        update_lenses(operation, &mut lenses);
     }
-    slots
-        .into_iter()
-        .enumerate()
-        .filter_map(|(i, opt)| opt.map(|lens_box| calculate_focusing_power(i, lens_box)))
-        .sum()
+    lenses.focusing_power()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::{Ok, Result};
+    use indoc::indoc;
     use pretty_assertions::assert_eq;
     use std::{fs::read_to_string, path::Path};
 
@@ -99,4 +311,108 @@ mod tests {
         let actual = day15_star2(&file);
         Ok(assert_eq!(actual, 258_826))
     }
+
+    #[test]
+    fn day15_trace_text() {
+        let trace = trace_lenses(EXAMPLE_INPUT).unwrap();
+        let actual = trace.iter().join("\n");
+        let expected = indoc! {r#"
+            After "rn=1":
+            Box 0: [rn 1]
+
+            After "cm-":
+            Box 0: [rn 1]
+
+            After "qp=3":
+            Box 0: [rn 1]
+            Box 1: [qp 3]
+
+            After "cm=2":
+            Box 0: [rn 1] [cm 2]
+            Box 1: [qp 3]
+
+            After "qp-":
+            Box 0: [rn 1] [cm 2]
+
+            After "pc=4":
+            Box 0: [rn 1] [cm 2]
+            Box 3: [pc 4]
+
+            After "ot=9":
+            Box 0: [rn 1] [cm 2]
+            Box 3: [pc 4] [ot 9]
+
+            After "ab=5":
+            Box 0: [rn 1] [cm 2]
+            Box 3: [pc 4] [ot 9] [ab 5]
+
+            After "pc-":
+            Box 0: [rn 1] [cm 2]
+            Box 3: [ot 9] [ab 5]
+
+            After "pc=6":
+            Box 0: [rn 1] [cm 2]
+            Box 3: [ot 9] [ab 5] [pc 6]
+
+            After "ot=7":
+            Box 0: [rn 1] [cm 2]
+            Box 3: [ot 7] [ab 5] [pc 6]
+        "#};
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn day15_trace_json_and_diff() {
+        let trace = trace_lenses(EXAMPLE_INPUT).unwrap();
+        let expected = r#"{"step":"qp=3","boxes":[{"box":0,"lenses":[{"label":"rn","focal_length":1}]},{"box":1,"lenses":[{"label":"qp","focal_length":3}]}]}"#;
+        assert_eq!(trace[2].to_json(), expected);
+        let actual = trace[4].diff(&trace[3]).iter().join("\n");
+        assert_eq!(actual, "Box 1: - [qp 3]");
+        let actual = trace[10].diff(&trace[9]).iter().join("\n");
+        assert_eq!(actual, "Box 3: [ot 9] -> [ot 7]");
+        assert!(trace[1].diff(&trace[0]).is_empty());
+    }
+
+    #[test]
+    fn day15_trims_steps() {
+        let input = "rn=1,cm-,\nqp=3 ,cm=2\n";
+        assert_eq!(day15_star1(input), day15_star1("rn=1,cm-,qp=3,cm=2"));
+        let actual = parse_operations(input).unwrap();
+        assert_eq!(actual[1], Operation::Remove { label: "cm" });
+        assert_eq!(
+            actual[2],
+            Operation::Insert {
+                label: "qp",
+                focal_length: 3
+            }
+        );
+    }
+
+    #[test]
+    fn day15_malformed_steps() {
+        let actual = parse_operations("rn=1,,cm-");
+        let expected = OperationError::MissingOperation {
+            position: 2,
+            step: String::new(),
+        };
+        assert_eq!(actual, Err(expected));
+        let actual = parse_operations("rn=1,-");
+        let expected = OperationError::InvalidLabel {
+            position: 2,
+            label: String::new(),
+        };
+        assert_eq!(actual, Err(expected));
+        let actual = parse_operations("rn=0");
+        let expected = OperationError::InvalidFocalLength {
+            position: 1,
+            focal_length: "0".to_owned(),
+        };
+        assert_eq!(actual, Err(expected));
+        let actual = parse_operations("rn=1,qp=10");
+        let expected = OperationError::InvalidFocalLength {
+            position: 2,
+            focal_length: "10".to_owned(),
+        };
+        assert_eq!(actual, Err(expected));
+    }
 }
@@ -1,17 +1,94 @@
-#![feature(if_let_guard, let_chains, iter_map_windows)]
+#![feature(if_let_guard, let_chains)]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery)]
 #![allow(
     dead_code,
     clippy::module_name_repetitions,
     clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
     clippy::cast_sign_loss
 )]
 
 mod common;
 mod days;
 
-fn main() {
-    println!("Hello, world!");
-    println!("Currently just using tests to run these functions,");
-    println!("but maybe I'll add some visualisations or something here!");
+use days::day12::nonogram::{render_picture, Nonogram, Solution};
+use std::{env, fs, io};
+
+const USAGE: &str = "\
+Usage:
+    advent-of-code-2023 day08-dot <input> <output> [start node]
+    advent-of-code-2023 day12-arrangements <record line> [limit]
+    advent-of-code-2023 nonogram <puzzle.non>
+    advent-of-code-2023 day15-trace <input> [text|json|diff]";
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["day08-dot", input, output] => write_day08_dot(input, output, None)?,
+        ["day08-dot", input, output, start] => write_day08_dot(input, output, Some(start))?,
+        ["day12-arrangements", line] => print_day12_arrangements(line, usize::MAX),
+        ["day12-arrangements", line, limit] if let Ok(limit) = limit.parse() => {
+            print_day12_arrangements(line, limit);
+        }
+        ["nonogram", input] => solve_nonogram(input)?,
+        ["day15-trace", input] => print_day15_trace(input, "text")?,
+        ["day15-trace", input, format @ ("text" | "json" | "diff")] => {
+            print_day15_trace(input, format)?;
+        }
+        _ => println!("{USAGE}"),
+    }
+    Ok(())
+}
+
+fn write_day08_dot(input: &str, output: &str, start: Option<&str>) -> io::Result<()> {
+    let input = fs::read_to_string(input)?;
+    let dot = days::day08::network_to_dot(&input, start)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    fs::write(output, dot)
+}
+
+fn print_day12_arrangements(line: &str, limit: usize) {
+    let mut count = 0;
+    for arrangement in days::day12::Arrangements::new(line).take(limit) {
+        println!("{arrangement}");
+        count += 1;
+    }
+    println!("{count} arrangements");
+}
+
+fn solve_nonogram(input: &str) -> io::Result<()> {
+    let input = fs::read_to_string(input)?;
+    let nonogram = Nonogram::from_non(&input)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    match nonogram.solve() {
+        Solution::None => println!("No solution"),
+        Solution::Unique(grid) => println!("Unique solution:\n{}", render_picture(&grid)),
+        Solution::Multiple(first, second) => println!(
+            "Multiple solutions, including:\n{}\n\n{}",
+            render_picture(&first),
+            render_picture(&second)
+        ),
+    }
+    Ok(())
+}
+
+fn print_day15_trace(input: &str, format: &str) -> io::Result<()> {
+    let input = fs::read_to_string(input)?;
+    let mut previous = days::day15::TraceStep::default();
+    let trace = days::day15::trace_lenses(&input)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    for step in trace {
+        match format {
+            "json" => println!("{}", step.to_json()),
+            "diff" => {
+                println!("After {:?}:", step.step);
+                for change in step.diff(&previous) {
+                    println!("{change}");
+                }
+            }
+            _ => println!("{step}"),
+        }
+        previous = step;
+    }
+    Ok(())
 }